@@ -1,55 +1,183 @@
+use crate::breaker::CircuitBreaker;
+use crate::error::{AppError, FallbackError, HttpError, NodeAttempt};
+use crate::health::{HealthTracker, SourceHealth};
+use crate::limiter::RateLimiter;
+use crate::network::{ConnectionTest, NetworkSettings, NetworkStore};
+use crate::sources::SourceStore;
 use crate::types::*;
 use anyhow::{anyhow, Result};
+use futures::future::join_all;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
-use std::time::Duration;
-
-/// API 端点配置
-const API_SOURCES: &[&str] = &[
-    "http://qkfqapi.vv9v.cn",
-    "http://49.232.137.12",
-    "http://43.248.77.205:22222",
-    "https://fq.shusan.cn",
-];
+#[cfg(feature = "gui")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager};
+
+/// 健康探测的超时时间
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// 下载封面的超时时间，失败时改用生成的封面，不值得久等
+const COVER_TIMEOUT: Duration = Duration::from_secs(15);
 
 /// 番茄小说 API 客户端
 pub struct FanqieApi {
-    client: Client,
-    base_url: String,
+    sources: SourceStore,
+    network: NetworkStore,
+    /// 按实际使用的代理缓存的客户端，同一代理下的请求共用连接池
+    clients: Mutex<HashMap<Option<String>, Client>>,
+    health: HealthTracker,
+    breaker: CircuitBreaker,
+    limiter: RateLimiter,
 }
 
 impl FanqieApi {
+    /// 使用内置的默认节点
     pub fn new() -> Self {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert("Referer", "https://fanqienovel.com/".parse().unwrap());
-        headers.insert("X-Requested-With", "XMLHttpRequest".parse().unwrap());
-        headers.insert("Accept-Language", "zh-CN,zh;q=0.9,en-US;q=0.8,en;q=0.7".parse().unwrap());
-        headers.insert("Accept", "application/json, text/javascript, */*; q=0.01".parse().unwrap());
-
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .connect_timeout(Duration::from_secs(10))
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .default_headers(headers)
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::with_sources(SourceStore::default())
+    }
 
+    /// 使用给定的节点配置，配置修改后对已创建的客户端同样生效
+    pub fn with_sources(sources: SourceStore) -> Self {
         Self {
-            client,
-            base_url: API_SOURCES[0].to_string(),
+            sources,
+            network: NetworkStore::default(),
+            clients: Mutex::default(),
+            health: HealthTracker::default(),
+            breaker: CircuitBreaker::default(),
+            limiter: RateLimiter::default(),
         }
     }
-    
-    // 省略 with_base_url ...
+
+    /// 应用共享的客户端，所有命令和下载共用连接池、健康统计和熔断状态
+    #[cfg(feature = "gui")]
+    pub fn for_app(app_handle: &AppHandle) -> Arc<Self> {
+        app_handle.state::<Arc<FanqieApi>>().inner().clone()
+    }
+
+    /// 使用给定的网络设置（代理、超时、请求头）
+    pub fn with_network(mut self, network: NetworkStore) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn network_settings(&self) -> NetworkSettings {
+        self.network.get()
+    }
+
+    /// 保存网络设置，之后的请求使用新设置重新建立连接
+    pub fn set_network_settings(&self, settings: NetworkSettings) -> Result<()> {
+        self.network.set(settings)?;
+        self.clients.lock().unwrap().clear();
+        Ok(())
+    }
+
+    /// 向节点发送请求的客户端，节点单独设置的代理优先于全局代理
+    fn client_for(&self, base_url: &str) -> Result<Client> {
+        let settings = self.network.get();
+        let proxy = self.sources.proxy(base_url).or(settings.proxy.clone());
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.get(&proxy) {
+            return Ok(client.clone());
+        }
+        let client = settings.build_client(proxy.as_deref())?;
+        clients.insert(proxy, client.clone());
+        Ok(client)
+    }
+
+    /// 用给定的网络设置测试节点连通性，不影响正在使用的设置；`base_url` 为空时测试所有已启用节点
+    pub async fn test_connection(
+        &self,
+        settings: &NetworkSettings,
+        base_url: Option<&str>,
+    ) -> Result<Vec<ConnectionTest>> {
+        settings.validate()?;
+        let base_urls = match base_url {
+            Some(base_url) => vec![base_url.to_string()],
+            None => self.sources.enabled_urls(),
+        };
+
+        Ok(join_all(base_urls.into_iter().map(|base_url| async move {
+            let proxy = self.sources.proxy(&base_url).or(settings.proxy.clone());
+            let started = Instant::now();
+            let result = match settings.build_client(proxy.as_deref()) {
+                Ok(client) => client.get(&base_url).send().await.map_err(anyhow::Error::from),
+                Err(e) => Err(e),
+            };
+            let latency_ms = started.elapsed().as_millis() as u64;
+            match result {
+                Ok(resp) => ConnectionTest {
+                    ok: !resp.status().is_server_error(),
+                    status: Some(resp.status().as_u16()),
+                    error: None,
+                    base_url,
+                    proxy,
+                    latency_ms,
+                },
+                Err(e) => ConnectionTest {
+                    ok: false,
+                    status: None,
+                    error: Some(format!("{:#}", e)),
+                    base_url,
+                    proxy,
+                    latency_ms,
+                },
+            }
+        }))
+        .await)
+    }
+
+    /// 探测所有节点并返回健康状况
+    pub async fn probe_sources(&self) -> Vec<SourceHealth> {
+        let base_urls: Vec<String> = self
+            .sources
+            .list()
+            .into_iter()
+            .map(|source| source.base_url)
+            .collect();
+
+        join_all(base_urls.iter().map(|base_url| async move {
+            let client = match self.client_for(base_url) {
+                Ok(client) => client,
+                Err(e) => return self.health.record_failure(base_url, &e.to_string()),
+            };
+            let started = Instant::now();
+            match client.get(base_url).timeout(PROBE_TIMEOUT).send().await {
+                Ok(resp) if !resp.status().is_server_error() => {
+                    self.health.record_success(base_url, started.elapsed())
+                }
+                Ok(resp) => self.health.record_failure(base_url, &format!("HTTP {}", resp.status())),
+                Err(e) => self.health.record_failure(base_url, &e.to_string()),
+            }
+        }))
+        .await;
+
+        self.source_health()
+    }
+
+    /// 当前的节点健康状况，按配置顺序排列
+    pub fn source_health(&self) -> Vec<SourceHealth> {
+        let base_urls: Vec<String> = self
+            .sources
+            .list()
+            .into_iter()
+            .map(|source| source.base_url)
+            .collect();
+        let mut health = self.health.snapshot(&base_urls);
+        for node in &mut health {
+            node.circuit_open = self.breaker.is_open(&node.base_url);
+        }
+        health
+    }
 
     /// 搜索书籍
     pub async fn search_books(&self, keyword: &str, offset: i32) -> Result<SearchResult> {
         let keyword = keyword.to_string();
-        let client = self.client.clone();
-        
-        self.try_with_fallback(move |base_url| {
+
+        self.try_with_fallback(move |client, base_url| {
             let keyword = keyword.clone();
-            let client = client.clone();
             let offset_str = offset.to_string();
             async move {
                 let url = format!("{}/api/search", base_url);
@@ -64,57 +192,28 @@ impl FanqieApi {
                     .send()
                     .await?;
 
-                let data: serde_json::Value = resp.json().await?;
-                
-                if data["code"].as_i64() != Some(200) {
-                    return Err(anyhow!("API 返回错误: {:?}", data["message"]));
-                }
+                let data: SearchData = read_api(resp, "search").await?;
 
-                // 从 search_tabs 中提取书籍数据
+                // 取第一个有数据的 tab
                 let mut books = Vec::new();
                 let mut has_more = false;
-                
-                if let Some(search_tabs) = data["data"]["search_tabs"].as_array() {
-                    for tab in search_tabs {
-                        if let Some(tab_data) = tab["data"].as_array() {
-                            if !tab_data.is_empty() {
-                                has_more = tab["has_more"].as_bool().unwrap_or(false);
-                                
-                                for item in tab_data {
-                                    // book_data 是一个数组，取第一个元素
-                                    let book_info = if let Some(book_data_arr) = item["book_data"].as_array() {
-                                        book_data_arr.get(0)
-                                    } else {
-                                        None
-                                    };
-                                    
-                                    let b = book_info.unwrap_or(item);
-                                    let book_id = item["book_id"].as_str()
-                                        .or_else(|| b["book_id"].as_str());
-                                    
-                                    if let Some(id) = book_id {
-                                        books.push(BookInfo {
-                                            book_id: id.to_string(),
-                                            book_name: b["book_name"].as_str().unwrap_or("未知").to_string(),
-                                            author: b["author"].as_str().unwrap_or("未知").to_string(),
-                                            cover_url: b["thumb_url"].as_str()
-                                                .or_else(|| b["cover_url"].as_str())
-                                                .unwrap_or("")
-                                                .to_string(),
-                                            description: b["abstract"].as_str().unwrap_or("").to_string(),
-                                            word_count: b["word_number"].as_i64()
-                                                .or_else(|| b["word_count"].as_i64()),
-                                            chapter_count: b["serial_count"].as_i64()
-                                                .or_else(|| b["chapter_number"].as_i64()),
-                                            category: b["category"].as_str().map(|s| s.to_string()),
-                                            status: b["creation_status"].as_str().map(|s| s.to_string()),
-                                        });
-                                    }
-                                }
-                                break; // 找到有数据的 tab 就停止
+                if let Some(tab) = data.search_tabs.into_iter().find(|tab| !tab.data.is_empty()) {
+                    has_more = tab.has_more;
+                    // 单个条目格式异常时跳过，只有全部无法解析才视为接口结构变化
+                    let mut error = None;
+                    for item in tab.data {
+                        match parse_search_item(item) {
+                            Ok(Some(book)) => books.push(book),
+                            Ok(None) => {}
+                            Err(e) => {
+                                eprintln!("跳过无法解析的搜索结果: {}", e);
+                                error.get_or_insert(e);
                             }
                         }
                     }
+                    if let Some(e) = error.filter(|_| books.is_empty()) {
+                        return Err(e);
+                    }
                 }
 
                 let total = books.len() as i64;
@@ -131,11 +230,9 @@ impl FanqieApi {
     /// 获取书籍详情
     pub async fn get_book_detail(&self, book_id: &str) -> Result<BookInfo> {
         let book_id = book_id.to_string();
-        let client = self.client.clone();
 
-        self.try_with_fallback(move |base_url| {
+        self.try_with_fallback(move |client, base_url| {
             let book_id = book_id.clone();
-            let client = client.clone();
             async move {
                 let url = format!("{}/api/detail", base_url);
                 
@@ -145,39 +242,19 @@ impl FanqieApi {
                     .send()
                     .await?;
 
-                let data: serde_json::Value = resp.json().await?;
-                
-                if data["code"].as_i64() != Some(200) {
-                    return Err(anyhow!("API 返回错误: {:?}", data["message"]));
-                }
-
-                // 处理嵌套的 data 结构
-                let book_data = if data["data"]["data"].is_object() {
-                    &data["data"]["data"]
-                } else {
-                    &data["data"]
-                };
+                let data: serde_json::Value = read_api(resp, "detail").await?;
 
-                // 检查书籍是否下架
-                if book_data["message"].as_str() == Some("BOOK_REMOVE") {
-                    return Err(anyhow!("书籍已下架"));
+                // 书籍数据可能嵌套在内层 data 中
+                let inner: BookDetailInner = parse("detail", data.clone())?;
+                if inner.message.as_deref() == Some("BOOK_REMOVE") {
+                    return Err(AppError::BookRemoved.into());
                 }
+                let book = match inner.data {
+                    Some(book) => book,
+                    None => parse::<BookData>("detail", data)?,
+                };
 
-                Ok(BookInfo {
-                    book_id: book_id.to_string(),
-                    book_name: book_data["book_name"].as_str().unwrap_or("未知").to_string(),
-                    author: book_data["author"].as_str().unwrap_or("未知").to_string(),
-                    cover_url: book_data["thumb_url"].as_str()
-                        .or_else(|| book_data["cover_url"].as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    description: book_data["abstract"].as_str().unwrap_or("").to_string(),
-                    word_count: book_data["word_count"].as_i64(),
-                    chapter_count: book_data["serial_count"].as_i64()
-                        .or_else(|| book_data["chapter_count"].as_i64()),
-                    category: book_data["category"].as_str().map(|s| s.to_string()),
-                    status: book_data["creation_status"].as_str().map(|s| s.to_string()),
-                })
+                Ok(book.into_book_info(book_id))
             }
         }).await
     }
@@ -197,11 +274,9 @@ impl FanqieApi {
     
     async fn try_directory_api(&self, book_id: &str) -> Result<Vec<Chapter>> {
         let book_id = book_id.to_string();
-        let client = self.client.clone();
 
-        self.try_with_fallback(move |base_url| {
+        self.try_with_fallback(move |client, base_url| {
             let book_id = book_id.clone();
-            let client = client.clone();
             async move {
                 let url = format!("{}/api/directory", base_url);
                 
@@ -211,24 +286,16 @@ impl FanqieApi {
                     .send()
                     .await?;
 
-                let data: serde_json::Value = resp.json().await?;
-                
-                if data["code"].as_i64() != Some(200) {
-                    return Err(anyhow!("directory API 返回错误"));
-                }
-
-                let lists = data["data"]["lists"].as_array()
-                    .ok_or_else(|| anyhow!("无法获取章节列表"))?;
+                let data: DirectoryData = read_api(resp, "directory").await?;
 
-                let chapters: Vec<Chapter> = lists
-                    .iter()
+                let chapters: Vec<Chapter> = data
+                    .lists
+                    .into_iter()
                     .enumerate()
-                    .filter_map(|(idx, ch)| {
-                        Some(Chapter {
-                            id: ch["item_id"].as_str()?.to_string(),
-                            title: ch["title"].as_str().unwrap_or("未知章节").to_string(),
-                            index: idx,
-                        })
+                    .map(|(idx, ch)| Chapter {
+                        id: ch.item_id,
+                        title: ch.title,
+                        index: idx,
                     })
                     .collect();
 
@@ -239,11 +306,9 @@ impl FanqieApi {
     
     async fn try_book_api(&self, book_id: &str) -> Result<Vec<Chapter>> {
         let book_id = book_id.to_string();
-        let client = self.client.clone();
 
-        self.try_with_fallback(move |base_url| {
+        self.try_with_fallback(move |client, base_url| {
             let book_id = book_id.clone();
-            let client = client.clone();
             async move {
                 let url = format!("{}/api/book", base_url);
                 
@@ -253,53 +318,38 @@ impl FanqieApi {
                     .send()
                     .await?;
 
-                let data: serde_json::Value = resp.json().await?;
-                
-                if data["code"].as_i64() != Some(200) {
-                    return Err(anyhow!("book API 返回错误: {:?}", data["message"]));
-                }
-                
-                let inner = &data["data"]["data"];
-                let mut chapters = Vec::new();
-                
-                // 尝试从 chapterListWithVolume 获取
-                if let Some(volumes) = inner["chapterListWithVolume"].as_array() {
-                    let mut idx = 0;
-                    for volume in volumes {
-                        if let Some(vol_chapters) = volume.as_array() {
-                            for ch in vol_chapters {
-                                let item_id = ch["itemId"].as_str()
-                                    .or_else(|| ch["item_id"].as_str());
-                                if let Some(id) = item_id {
-                                    chapters.push(Chapter {
-                                        id: id.to_string(),
-                                        title: ch["title"].as_str().unwrap_or("未知章节").to_string(),
-                                        index: idx,
-                                    });
-                                    idx += 1;
-                                }
-                            }
-                        }
-                    }
-                }
+                let data: BookApiData = read_api(resp, "book").await?;
+                let inner = data.data;
+
+                // 优先使用 chapterListWithVolume
+                let mut chapters: Vec<Chapter> = inner
+                    .volumes
+                    .into_iter()
+                    .flatten()
+                    .enumerate()
+                    .map(|(idx, ch)| Chapter {
+                        id: ch.item_id,
+                        title: ch.title,
+                        index: idx,
+                    })
+                    .collect();
                 
                 // 如果还是空的，尝试从 allItemIds 生成
                 if chapters.is_empty() {
-                    if let Some(ids) = inner["allItemIds"].as_array() {
-                        for (idx, id) in ids.iter().enumerate() {
-                            if let Some(id_str) = id.as_str() {
-                                chapters.push(Chapter {
-                                    id: id_str.to_string(),
-                                    title: format!("第{}章", idx + 1),
-                                    index: idx,
-                                });
-                            }
-                        }
-                    }
+                    chapters = inner
+                        .item_ids
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, id)| Chapter {
+                            id,
+                            title: format!("第{}章", idx + 1),
+                            index: idx,
+                        })
+                        .collect();
                 }
                 
                 if chapters.is_empty() {
-                    return Err(anyhow!("无法从任何 API 获取章节列表"));
+                    return Err(AppError::NoChapters.into());
                 }
 
                 Ok(chapters)
@@ -310,11 +360,9 @@ impl FanqieApi {
     /// 获取单个章节内容
     pub async fn get_chapter_content(&self, item_id: &str) -> Result<String> {
         let item_id = item_id.to_string(); // Clone for closure
-        let client = self.client.clone();
-        
-        self.try_with_fallback(move |base_url| {
+
+        self.try_with_fallback(move |client, base_url| {
             let item_id = item_id.clone();
-            let client = client.clone();
             async move {
                 let url = format!("{}/api/content", base_url);
                 let resp = client
@@ -326,33 +374,45 @@ impl FanqieApi {
                     .send()
                     .await?;
 
-                let data: serde_json::Value = resp.json().await?;
-                
-                if data["code"].as_i64() != Some(200) {
-                    return Err(anyhow!("API 返回错误: {:?}", data["message"]));
-                }
-
-                let content = data["data"]["content"].as_str()
-                    .or_else(|| data["data"].as_str())
-                    .unwrap_or("");
+                let content = match read_api(resp, "content").await? {
+                    ContentData::Text(content) => content,
+                    ContentData::Content(data) => data.content.unwrap_or_default(),
+                };
                     
                 if content.trim().is_empty() {
                     return Err(anyhow!("内容为空"));
                 }
 
-                Ok(process_content(content))
+                Ok(process_content(&content))
             }
         }).await
     }
 
+    /// 下载封面图片，封面在图片服务器上，不经过 API 节点
+    pub async fn get_cover(&self, url: &str) -> Result<Vec<u8>> {
+        let resp = self
+            .client_for(url)?
+            .get(url)
+            .timeout(COVER_TIMEOUT)
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(HttpError {
+                status: status.as_u16(),
+                message: status.canonical_reason().unwrap_or("请求失败").to_string(),
+            }
+            .into());
+        }
+        Ok(resp.bytes().await?.to_vec())
+    }
+
     /// 极速模式 - 获取整本书内容
     pub async fn get_full_content(&self, book_id: &str) -> Result<HashMap<String, String>> {
         let book_id = book_id.to_string();
-        let client = self.client.clone();
 
-        self.try_with_fallback(move |base_url| {
+        self.try_with_fallback(move |client, base_url| {
             let book_id = book_id.clone();
-            let client = client.clone();
             async move {
                 // 尝试批量模式
                 let url = format!("{}/api/content", base_url);
@@ -366,25 +426,15 @@ impl FanqieApi {
                     .send()
                     .await?;
 
-                let data: serde_json::Value = resp.json().await?;
-                
-                if data["code"].as_i64() != Some(200) {
-                    return Err(anyhow!("极速模式不可用"));
-                }
-
-                let mut content_map = HashMap::new();
+                let data: BatchContentData = read_api(resp, "content")
+                    .await
+                    .map_err(|e| e.context("极速模式不可用"))?;
 
-                // 尝试解析批量内容
-                if let Some(lists) = data["data"]["lists"].as_array() {
-                    for item in lists {
-                        if let (Some(id), Some(content)) = (
-                            item["item_id"].as_str(),
-                            item["content"].as_str()
-                        ) {
-                            content_map.insert(id.to_string(), process_content(content));
-                        }
-                    }
-                }
+                let content_map: HashMap<String, String> = data
+                    .lists
+                    .into_iter()
+                    .filter_map(|item| Some((item.item_id, process_content(&item.content?))))
+                    .collect();
 
                 if content_map.is_empty() {
                     return Err(anyhow!("批量模式返回空内容"));
@@ -395,19 +445,86 @@ impl FanqieApi {
         }).await
     }
 
-    /// 尝试多个 API 节点
+    /// 按健康状况依次尝试已启用的 API 节点
     pub async fn try_with_fallback<F, T, Fut>(&self, operation: F) -> Result<T>
     where
-        F: Fn(String) -> Fut,
+        F: Fn(Client, String) -> Fut,
         Fut: std::future::Future<Output = Result<T>>,
     {
-        for base_url in API_SOURCES {
-            match operation(base_url.to_string()).await {
-                Ok(result) => return Ok(result),
-                Err(_) => continue,
+        let sources = self.health.rank(&self.sources.enabled_urls());
+        let mut attempts = Vec::with_capacity(sources.len());
+        let mut app_errors = Vec::new();
+        for base_url in sources {
+            if !self.breaker.allow(&base_url) {
+                attempts.push(NodeAttempt {
+                    base_url,
+                    status: None,
+                    elapsed_ms: 0,
+                    error: "节点熔断中，已跳过".to_string(),
+                    skipped: true,
+                });
+                continue;
+            }
+            let client = match self.client_for(&base_url) {
+                Ok(client) => client,
+                Err(e) => {
+                    attempts.push(NodeAttempt {
+                        base_url,
+                        status: None,
+                        elapsed_ms: 0,
+                        error: e.to_string(),
+                        skipped: false,
+                    });
+                    app_errors.push(e);
+                    continue;
+                }
+            };
+            self.limiter
+                .acquire(&base_url, self.sources.rate_limit(&base_url))
+                .await;
+            let started = Instant::now();
+            match operation(client, base_url.clone()).await {
+                Ok(result) => {
+                    self.health.record_success(&base_url, started.elapsed());
+                    self.breaker.record_success(&base_url);
+                    self.limiter.record_success(&base_url);
+                    return Ok(result);
+                }
+                Err(e) => {
+                    // 书籍下架由服务端判定，换节点也没有意义
+                    if matches!(e.downcast_ref::<AppError>(), Some(AppError::BookRemoved)) {
+                        self.breaker.record_success(&base_url);
+                        return Err(e);
+                    }
+                    self.health.record_failure(&base_url, &e.to_string());
+                    // 只有网络或 HTTP 层面的错误才计入熔断和降速，业务错误（如书籍下架）与节点无关
+                    if e.is::<reqwest::Error>() || e.is::<HttpError>() {
+                        self.breaker.record_failure(&base_url);
+                        self.limiter.record_failure(&base_url, response_status(&e));
+                    } else {
+                        self.breaker.record_success(&base_url);
+                    }
+                    attempts.push(NodeAttempt {
+                        base_url,
+                        status: response_status(&e),
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                        error: e.to_string(),
+                        skipped: false,
+                    });
+                    app_errors.push(e);
+                }
             }
         }
-        Err(anyhow!("所有 API 节点均不可用"))
+
+        // 所有节点都给出同一种业务错误时直接返回该错误
+        let codes: Vec<_> = app_errors
+            .iter()
+            .map(|e| e.downcast_ref::<AppError>().map(AppError::code))
+            .collect();
+        if !codes.is_empty() && codes[0].is_some() && codes.iter().all(|code| *code == codes[0]) {
+            return Err(app_errors.swap_remove(0));
+        }
+        Err(FallbackError { attempts }.into())
     }
 }
 
@@ -417,6 +534,70 @@ impl Default for FanqieApi {
     }
 }
 
+/// 读取接口响应中的 data，`code` 不为 200 时返回接口给出的错误信息
+async fn read_api<T: DeserializeOwned>(resp: reqwest::Response, endpoint: &str) -> Result<T> {
+    let resp: ApiResponse<serde_json::Value> = parse(endpoint, read_json(resp).await?)?;
+    if resp.code != 200 {
+        return Err(anyhow!(
+            "{} 接口返回错误: {}",
+            endpoint,
+            resp.message.unwrap_or_else(|| resp.code.to_string())
+        ));
+    }
+    parse(endpoint, resp.data.unwrap_or_default())
+}
+
+/// 按接口模型解析，字段缺失或类型不符时视为接口结构变化
+/// 解析一个搜索结果条目，不是书籍的条目返回 `None`
+fn parse_search_item(item: serde_json::Value) -> Result<Option<BookInfo>> {
+    let search_item: SearchItem = parse("search", item.clone())?;
+    // book_data 是一个数组，取第一个元素；没有时条目本身就是书籍数据
+    let book = search_item.book_data.into_iter().next().unwrap_or(item);
+    let book_id = match search_item.book_id {
+        Some(id) => id,
+        None => match parse::<SearchItem>("search", book.clone())?.book_id {
+            Some(id) => id,
+            // 没有 book_id 的条目不是书籍
+            None => return Ok(None),
+        },
+    };
+    let book: BookData = parse("search", book)?;
+    Ok(Some(book.into_book_info(book_id)))
+}
+
+fn parse<T: DeserializeOwned>(endpoint: &str, value: serde_json::Value) -> Result<T> {
+    serde_json::from_value(value).map_err(|e| AppError::SchemaChanged(format!("{}: {}", endpoint, e)).into())
+}
+
+/// 读取 JSON 响应，状态码异常或无法解析时返回 [`HttpError`]
+async fn read_json(resp: reqwest::Response) -> Result<serde_json::Value> {
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(HttpError {
+            status: status.as_u16(),
+            message: status.canonical_reason().unwrap_or("请求失败").to_string(),
+        }
+        .into());
+    }
+    resp.json().await.map_err(|e| {
+        HttpError {
+            status: status.as_u16(),
+            message: format!("响应不是有效的 JSON: {}", e),
+        }
+        .into()
+    })
+}
+
+/// 从错误中取出 HTTP 状态码
+fn response_status(e: &anyhow::Error) -> Option<u16> {
+    if let Some(e) = e.downcast_ref::<HttpError>() {
+        return Some(e.status);
+    }
+    e.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .map(|status| status.as_u16())
+}
+
 /// 处理章节内容，清理 HTML 标签等
 fn process_content(content: &str) -> String {
     use regex::Regex;
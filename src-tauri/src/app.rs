@@ -0,0 +1,88 @@
+//! 桌面应用入口，只在启用 `gui` 特性时编译
+
+use crate::api::FanqieApi;
+use crate::cache::ResponseCache;
+use crate::chapter_store::ChapterStore;
+use crate::commands::{
+    add_api_source, cancel_download, check_updates, clear_cache, download_book, enqueue_download,
+    export_book, follow_book, get_api_sources, get_book_detail, get_chapters, get_library_entry,
+    get_network_settings, get_source_health, list_followed, list_library, list_queue,
+    pause_download, remove_api_source, remove_from_queue, remove_library_entry,
+    reorder_api_sources, reorder_queue, resume_download, search_books,
+    set_api_source_enabled, set_api_source_proxy, set_api_source_rate_limit,
+    set_network_settings, set_queue_concurrency, test_connection, unfollow_book, update_book,
+};
+use crate::control::DownloadRegistry;
+use crate::library::Library;
+use crate::network::NetworkStore;
+use crate::queue::DownloadQueue;
+use crate::sources::SourceStore;
+use crate::{health, paths, updater};
+use std::sync::Arc;
+use tauri::Manager;
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_fs::init())
+        .manage(DownloadRegistry::default())
+        .setup(|app| {
+            let sources = SourceStore::load(paths::app_data_path(app.handle(), "sources.json")?);
+            let network = NetworkStore::load(paths::app_data_path(app.handle(), "network.json")?);
+            app.manage(Arc::new(FanqieApi::with_sources(sources.clone()).with_network(network)));
+            app.manage(sources);
+            app.manage(ResponseCache::open(paths::app_data_path(app.handle(), "cache")?));
+            app.manage(ChapterStore::new(paths::app_data_path(app.handle(), "chapters")?));
+            health::spawn_prober(app.handle().clone());
+
+            let library = Library::open(&paths::app_data_path(app.handle(), "library.db")?)?;
+            app.manage(library);
+
+            let queue_path = paths::app_data_path(app.handle(), "queue.json")?;
+            let queue = DownloadQueue::load(app.handle().clone(), queue_path);
+            queue.start();
+            app.manage(queue);
+
+            updater::spawn_scheduler(app.handle().clone());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            search_books,
+            get_book_detail,
+            get_chapters,
+            download_book,
+            cancel_download,
+            pause_download,
+            resume_download,
+            update_book,
+            enqueue_download,
+            list_queue,
+            reorder_queue,
+            remove_from_queue,
+            set_queue_concurrency,
+            list_library,
+            get_library_entry,
+            remove_library_entry,
+            export_book,
+            follow_book,
+            unfollow_book,
+            list_followed,
+            check_updates,
+            get_api_sources,
+            get_source_health,
+            add_api_source,
+            remove_api_source,
+            set_api_source_enabled,
+            set_api_source_rate_limit,
+            set_api_source_proxy,
+            get_network_settings,
+            set_network_settings,
+            test_connection,
+            clear_cache,
+            reorder_api_sources,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
@@ -0,0 +1,379 @@
+//! 番茄小说下载器命令行版本，与桌面端共用节点配置、断点和本地书库
+
+use anyhow::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use tomato_novel_manager_lib::api::FanqieApi;
+use tomato_novel_manager_lib::book_source::BookSource;
+use tomato_novel_manager_lib::cache::{CachedSource, ResponseCache};
+use tomato_novel_manager_lib::chapter_store::ChapterStore;
+use tomato_novel_manager_lib::checkpoint::CheckpointStore;
+use tomato_novel_manager_lib::control::DownloadControl;
+use tomato_novel_manager_lib::downloader::Downloader;
+use tomato_novel_manager_lib::error::AppError;
+use tomato_novel_manager_lib::library::Library;
+use tomato_novel_manager_lib::network::NetworkStore;
+use tomato_novel_manager_lib::paths::data_dir;
+use tomato_novel_manager_lib::progress::ProgressSink;
+use tomato_novel_manager_lib::sources::SourceStore;
+use tomato_novel_manager_lib::types::*;
+
+#[derive(Parser)]
+#[command(name = "tomato-cli", version, about = "番茄小说下载器命令行版本")]
+struct Cli {
+    /// 以 JSON 输出结果
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// 跳过搜索、详情和目录缓存
+    #[arg(long, global = true)]
+    refresh: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 搜索书籍
+    Search {
+        keyword: String,
+        #[arg(long, default_value_t = 0)]
+        offset: i32,
+    },
+    /// 查看书籍详情
+    Info { book_id: String },
+    /// 列出章节目录
+    Chapters { book_id: String },
+    /// 下载书籍
+    Download {
+        book_id: String,
+        /// 保存路径，默认为当前目录下的「书名.格式」
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(short, long, value_enum, default_value_t = Format::Txt)]
+        format: Format,
+        /// 起始章节（从 0 开始）
+        #[arg(long)]
+        start: Option<usize>,
+        /// 结束章节（不含）
+        #[arg(long)]
+        end: Option<usize>,
+        /// 同时进行的章节请求数
+        #[arg(long)]
+        concurrency: Option<usize>,
+        /// 失败章节的重试轮数（最多 5 轮）
+        #[arg(long)]
+        retry: Option<u32>,
+        /// 有章节缺失时整个下载视为失败，默认用占位文本代替
+        #[arg(long)]
+        fail_on_missing: bool,
+    },
+    /// 增量更新书库中已下载的书籍
+    Update { book_id: String },
+    /// 不联网，用本地保存的章节把已下载的书籍导出为其他格式
+    Export {
+        book_id: String,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = Format::Epub)]
+        format: Format,
+        /// 本地缺少章节时用占位文本代替，默认导出失败
+        #[arg(long)]
+        allow_missing: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Txt,
+    Epub,
+}
+
+impl Format {
+    fn as_str(self) -> &'static str {
+        match self {
+            Format::Txt => "txt",
+            Format::Epub => "epub",
+        }
+    }
+}
+
+/// 在终端显示进度条
+struct BarProgress(ProgressBar);
+
+impl BarProgress {
+    fn new() -> Self {
+        let bar = ProgressBar::new(100);
+        bar.set_style(
+            ProgressStyle::with_template("{bar:40.cyan/blue} {pos:>3}% {msg}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        Self(bar)
+    }
+}
+
+impl ProgressSink for BarProgress {
+    fn report(&self, progress: DownloadProgress) {
+        self.0.set_position(progress.percent as u64);
+        self.0.set_message(progress.message);
+    }
+}
+
+/// 与桌面端相同的数据目录、节点配置和缓存
+struct Context {
+    api: Arc<FanqieApi>,
+    cache: ResponseCache,
+    chapters: ChapterStore,
+    library: Library,
+    checkpoints: CheckpointStore,
+}
+
+impl Context {
+    fn open() -> Result<Self> {
+        let dir = data_dir()?;
+        Ok(Self {
+            api: Arc::new(
+                FanqieApi::with_sources(SourceStore::load(dir.join("sources.json")))
+                    .with_network(NetworkStore::load(dir.join("network.json"))),
+            ),
+            cache: ResponseCache::open(dir.join("cache")),
+            chapters: ChapterStore::new(dir.join("chapters")),
+            library: Library::open(&dir.join("library.db"))?,
+            checkpoints: CheckpointStore::new(dir.join("checkpoints")),
+        })
+    }
+
+    /// 带缓存的书源，`refresh` 为 true 时跳过缓存
+    fn source(&self, refresh: bool) -> Arc<dyn BookSource> {
+        Arc::new(
+            CachedSource::new(self.api.clone(), self.cache.clone())
+                .with_chapters(self.chapters.clone())
+                .bypass(refresh),
+        )
+    }
+
+    /// 绑定 Ctrl-C 的下载器，中断时保留断点
+    fn downloader(&self, refresh: bool) -> Downloader {
+        let control = Arc::new(DownloadControl::new());
+        let on_interrupt = control.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                on_interrupt.cancel();
+            }
+        });
+
+        Downloader::with_source(self.source(refresh))
+            .with_checkpoints(self.checkpoints.clone())
+            .with_chapters(self.chapters.clone())
+            .with_library(self.library.clone())
+            .with_control(control)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command, cli.json, cli.refresh).await {
+        Ok(code) => code,
+        Err(e) => {
+            let e = AppError::from(e);
+            if cli.json {
+                print_json(&e);
+            } else {
+                eprintln!("错误：{}", e);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command, json: bool, refresh: bool) -> Result<ExitCode> {
+    let ctx = Context::open()?;
+    let source = ctx.source(refresh);
+
+    match command {
+        Command::Search { keyword, offset } => {
+            let result = source.search_books(&keyword, offset).await?;
+            if json {
+                print_json(&result);
+            } else {
+                for book in &result.books {
+                    println!(
+                        "{}  {}  {}  {}",
+                        book.book_id,
+                        book.book_name,
+                        book.author,
+                        book.chapter_count
+                            .map_or("-".to_string(), |n| format!("{}章", n))
+                    );
+                }
+                if result.has_more {
+                    println!(
+                        "（还有更多结果，使用 --offset {} 查看）",
+                        offset + result.books.len() as i32
+                    );
+                }
+            }
+        }
+        Command::Info { book_id } => {
+            let book = source.get_book_detail(&book_id).await?;
+            if json {
+                print_json(&book);
+            } else {
+                println!("书名：{}", book.book_name);
+                println!("作者：{}", book.author);
+                if let Some(category) = &book.category {
+                    println!("分类：{}", category);
+                }
+                if let Some(count) = book.chapter_count {
+                    println!("章节：{}", count);
+                }
+                if let Some(count) = book.word_count {
+                    println!("字数：{}", count);
+                }
+                if !book.description.is_empty() {
+                    println!("\n{}", book.description);
+                }
+            }
+        }
+        Command::Chapters { book_id } => {
+            let chapters = source.get_directory(&book_id).await?;
+            if json {
+                print_json(&chapters);
+            } else {
+                for ch in &chapters {
+                    println!("{:>5}  {}  {}", ch.index, ch.id, ch.title);
+                }
+            }
+        }
+        Command::Download {
+            book_id,
+            output,
+            format,
+            start,
+            end,
+            concurrency,
+            retry,
+            fail_on_missing,
+        } => {
+            let save_path = match output {
+                Some(path) => path,
+                None => {
+                    let book = source.get_book_detail(&book_id).await?;
+                    PathBuf::from(format!(
+                        "{}.{}",
+                        file_stem(&book.book_name),
+                        format.as_str()
+                    ))
+                }
+            };
+            let options = DownloadOptions {
+                book_id,
+                save_path: save_path.to_string_lossy().to_string(),
+                format: format.as_str().to_string(),
+                start_chapter: start,
+                end_chapter: end,
+                concurrency,
+                retry_rounds: retry,
+                missing_chapters: fail_on_missing.then_some(MissingChapterPolicy::Fail),
+            };
+
+            let progress = BarProgress::new();
+            let result = ctx.downloader(refresh).download(options, &progress).await;
+            progress.0.finish_and_clear();
+            return Ok(report(&result?, json));
+        }
+        Command::Update { book_id } => {
+            let progress = BarProgress::new();
+            // 增量更新总是需要最新的目录
+            let result = ctx.downloader(true).update(&book_id, &progress).await;
+            progress.0.finish_and_clear();
+            let update = result?;
+            if json {
+                print_json(&update);
+                return Ok(exit_code(&update.result));
+            }
+            if update.result.success {
+                println!("新增 {} 章", update.new_chapters);
+            }
+            return Ok(report(&update.result, false));
+        }
+        Command::Export {
+            book_id,
+            output,
+            format,
+            allow_missing,
+        } => {
+            let options = ExportOptions {
+                missing_chapters: allow_missing.then_some(MissingChapterPolicy::Placeholder),
+                ..ExportOptions::default()
+            };
+            let result = ctx.downloader(false).export(
+                &book_id,
+                format.as_str(),
+                &output.to_string_lossy(),
+                &options,
+            )?;
+            return Ok(report(&result, json));
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// 输出下载结果，只有完成时才返回成功
+fn report(result: &DownloadResult, json: bool) -> ExitCode {
+    if json {
+        print_json(result);
+    } else {
+        match result.status {
+            DownloadStatus::Completed => {
+                if let Some(path) = &result.file_path {
+                    println!("《{}》已保存到 {}", result.book_name, path);
+                }
+            }
+            DownloadStatus::Failed | DownloadStatus::Cancelled => {
+                eprintln!("{}", result.error.as_deref().unwrap_or("下载失败"));
+            }
+        }
+        if !result.failed_chapters.is_empty() {
+            eprintln!("以下 {} 个章节下载失败：", result.failed_chapters.len());
+            for ch in &result.failed_chapters {
+                eprintln!("  {}  {}", ch.title, ch.error);
+            }
+        }
+    }
+
+    exit_code(result)
+}
+
+fn exit_code(result: &DownloadResult) -> ExitCode {
+    match result.status {
+        DownloadStatus::Completed => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(text) => println!("{}", text),
+        Err(e) => eprintln!("错误：{}", e),
+    }
+}
+
+/// 去掉书名中不能出现在文件名里的字符
+fn file_stem(book_name: &str) -> String {
+    book_name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
@@ -0,0 +1,78 @@
+use crate::api::FanqieApi;
+#[cfg(feature = "gui")]
+use crate::cache::CachedSource;
+use crate::types::{BookInfo, Chapter, SearchResult};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+#[cfg(feature = "gui")]
+use std::sync::Arc;
+#[cfg(feature = "gui")]
+use tauri::AppHandle;
+
+/// 书源
+///
+/// 下载器、更新检查只依赖这个 trait，接入新的书源或测试替身时不需要修改它们。
+#[async_trait]
+pub trait BookSource: Send + Sync {
+    /// 搜索书籍
+    async fn search_books(&self, keyword: &str, offset: i32) -> Result<SearchResult>;
+
+    /// 获取书籍详情
+    async fn get_book_detail(&self, book_id: &str) -> Result<BookInfo>;
+
+    /// 获取章节目录
+    async fn get_directory(&self, book_id: &str) -> Result<Vec<Chapter>>;
+
+    /// 获取单个章节内容
+    async fn get_chapter_content(&self, item_id: &str) -> Result<String>;
+
+    /// 一次获取整本书内容，key 为章节 id；不支持时返回错误，下载器会改为逐章下载
+    async fn get_full_content(&self, _book_id: &str) -> Result<HashMap<String, String>> {
+        Err(anyhow!("该书源不支持批量获取"))
+    }
+
+    /// 下载封面图片；不支持时返回错误，EPUB 会改用生成的标题卡片
+    async fn get_cover(&self, _url: &str) -> Result<Vec<u8>> {
+        Err(anyhow!("该书源不支持获取封面"))
+    }
+}
+
+/// 应用使用的书源，搜索、详情和目录优先使用缓存
+#[cfg(feature = "gui")]
+pub fn for_app(app_handle: &AppHandle) -> Arc<dyn BookSource> {
+    Arc::new(CachedSource::for_app(app_handle))
+}
+
+/// 跳过缓存的书源，用于检查更新等需要最新数据的场景，结果仍会刷新缓存
+#[cfg(feature = "gui")]
+pub fn fresh_for_app(app_handle: &AppHandle) -> Arc<dyn BookSource> {
+    Arc::new(CachedSource::for_app(app_handle).bypass(true))
+}
+
+#[async_trait]
+impl BookSource for FanqieApi {
+    async fn search_books(&self, keyword: &str, offset: i32) -> Result<SearchResult> {
+        FanqieApi::search_books(self, keyword, offset).await
+    }
+
+    async fn get_book_detail(&self, book_id: &str) -> Result<BookInfo> {
+        FanqieApi::get_book_detail(self, book_id).await
+    }
+
+    async fn get_directory(&self, book_id: &str) -> Result<Vec<Chapter>> {
+        FanqieApi::get_directory(self, book_id).await
+    }
+
+    async fn get_chapter_content(&self, item_id: &str) -> Result<String> {
+        FanqieApi::get_chapter_content(self, item_id).await
+    }
+
+    async fn get_full_content(&self, book_id: &str) -> Result<HashMap<String, String>> {
+        FanqieApi::get_full_content(self, book_id).await
+    }
+
+    async fn get_cover(&self, url: &str) -> Result<Vec<u8>> {
+        FanqieApi::get_cover(self, url).await
+    }
+}
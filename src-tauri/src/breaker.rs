@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 连续失败多少次后熔断
+const FAILURE_THRESHOLD: u32 = 3;
+/// 熔断后多久允许试探请求
+const COOL_DOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// 正常放行，记录连续失败次数
+    Closed { failures: u32 },
+    /// 熔断中，到期前跳过该节点
+    Open { until: Instant },
+    /// 冷却结束，只放行一个试探请求
+    HalfOpen { since: Instant },
+}
+
+/// 节点熔断器
+///
+/// 连续失败的节点会被暂时跳过，冷却结束后放行一个试探请求，成功则恢复，
+/// 失败则重新熔断。克隆后共享同一份状态，供并发请求和多个下载任务共用。
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreaker {
+    nodes: Arc<Mutex<HashMap<String, State>>>,
+}
+
+impl CircuitBreaker {
+    /// 是否可以向该节点发送请求
+    pub fn allow(&self, base_url: &str) -> bool {
+        let mut nodes = self.nodes.lock().unwrap();
+        let now = Instant::now();
+        let state = nodes
+            .entry(base_url.to_string())
+            .or_insert(State::Closed { failures: 0 });
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { until } if now >= until => {
+                *state = State::HalfOpen { since: now };
+                true
+            }
+            State::Open { .. } => false,
+            // 试探请求被取消时不会回报结果，超过冷却时间后再放行一个
+            State::HalfOpen { since } if now.duration_since(since) >= COOL_DOWN => {
+                *state = State::HalfOpen { since: now };
+                true
+            }
+            State::HalfOpen { .. } => false,
+        }
+    }
+
+    pub fn record_success(&self, base_url: &str) {
+        self.nodes
+            .lock()
+            .unwrap()
+            .insert(base_url.to_string(), State::Closed { failures: 0 });
+    }
+
+    pub fn record_failure(&self, base_url: &str) {
+        let mut nodes = self.nodes.lock().unwrap();
+        let state = nodes
+            .entry(base_url.to_string())
+            .or_insert(State::Closed { failures: 0 });
+        let failures = match *state {
+            State::Closed { failures } => failures + 1,
+            _ => FAILURE_THRESHOLD,
+        };
+        *state = if failures >= FAILURE_THRESHOLD {
+            State::Open {
+                until: Instant::now() + COOL_DOWN,
+            }
+        } else {
+            State::Closed { failures }
+        };
+    }
+
+    /// 节点当前是否处于熔断中
+    pub fn is_open(&self, base_url: &str) -> bool {
+        matches!(
+            self.nodes.lock().unwrap().get(base_url),
+            Some(State::Open { until }) if Instant::now() < *until
+        )
+    }
+}
@@ -0,0 +1,353 @@
+#[cfg(feature = "gui")]
+use crate::api::FanqieApi;
+use crate::book_source::BookSource;
+use crate::chapter_store::ChapterStore;
+use crate::error::FallbackError;
+use crate::library::unix_now;
+use crate::paths::write_atomic;
+use crate::types::{BookInfo, Chapter, SearchResult};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager};
+
+/// 缓存的数据类型，各自有不同的有效期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    Search,
+    Detail,
+    Directory,
+}
+
+/// 过期的缓存在磁盘上继续保留的时长（秒），供无法联网时使用
+const STALE_RETENTION: u64 = 7 * 24 * 60 * 60;
+
+impl CacheKind {
+    const ALL: [CacheKind; 3] = [CacheKind::Search, CacheKind::Detail, CacheKind::Directory];
+
+    fn name(self) -> &'static str {
+        match self {
+            CacheKind::Search => "search",
+            CacheKind::Detail => "detail",
+            CacheKind::Directory => "directory",
+        }
+    }
+
+    /// 有效期（秒），目录随连载更新变化较快
+    fn ttl(self) -> u64 {
+        match self {
+            CacheKind::Search => 10 * 60,
+            CacheKind::Detail => 60 * 60,
+            CacheKind::Directory => 10 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    stored_at: u64,
+    value: serde_json::Value,
+}
+
+impl CacheEntry {
+    fn age(&self) -> u64 {
+        unix_now().saturating_sub(self.stored_at)
+    }
+
+    fn is_fresh(&self, kind: CacheKind) -> bool {
+        self.age() < kind.ttl()
+    }
+
+    /// 过期且超过保留时长，可以删除
+    fn is_obsolete(&self, kind: CacheKind) -> bool {
+        self.age() >= kind.ttl() + STALE_RETENTION
+    }
+}
+
+/// 搜索结果、书籍详情和章节目录的缓存
+///
+/// 内存中只保存未过期的条目，通过 [`ResponseCache::open`] 打开时同时写入磁盘，重启后仍然
+/// 有效。磁盘上过期的条目再保留一段时间供离线使用，之后在读取或打开时删除。克隆后共享同一份
+/// 状态。
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache {
+    dir: Option<PathBuf>,
+    memory: Arc<Mutex<HashMap<(CacheKind, String), CacheEntry>>>,
+}
+
+impl ResponseCache {
+    /// 使用 `dir` 作为磁盘缓存目录，同时删除已经失效的缓存文件
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        let cache = Self {
+            dir: Some(dir.into()),
+            memory: Arc::default(),
+        };
+        cache.prune_disk();
+        cache
+    }
+
+    /// 读取未过期的缓存
+    pub fn get<T: DeserializeOwned>(&self, kind: CacheKind, key: &str) -> Option<T> {
+        self.lookup(kind, key, true)
+    }
+
+    /// 读取缓存，即使已经过期，用于无法联网时
+    pub fn get_stale<T: DeserializeOwned>(&self, kind: CacheKind, key: &str) -> Option<T> {
+        self.lookup(kind, key, false)
+    }
+
+    fn lookup<T: DeserializeOwned>(
+        &self,
+        kind: CacheKind,
+        key: &str,
+        fresh_only: bool,
+    ) -> Option<T> {
+        let cache_key = (kind, key.to_string());
+        let mut memory = self.memory.lock().unwrap();
+        let entry = match memory.get(&cache_key) {
+            Some(entry) if entry.is_fresh(kind) => entry.clone(),
+            _ => {
+                memory.remove(&cache_key);
+                let entry = self.read_disk(kind, key)?;
+                if entry.is_fresh(kind) {
+                    memory.insert(cache_key, entry.clone());
+                } else if fresh_only {
+                    return None;
+                }
+                entry
+            }
+        };
+        serde_json::from_value(entry.value).ok()
+    }
+
+    pub fn put<T: Serialize>(&self, kind: CacheKind, key: &str, value: &T) {
+        let Ok(value) = serde_json::to_value(value) else {
+            return;
+        };
+        let entry = CacheEntry {
+            stored_at: unix_now(),
+            value,
+        };
+        if let Err(e) = self.write_disk(kind, key, &entry) {
+            eprintln!("写入缓存失败: {}", e);
+        }
+        let mut memory = self.memory.lock().unwrap();
+        memory.retain(|(kind, _), entry| entry.is_fresh(*kind));
+        memory.insert((kind, key.to_string()), entry);
+    }
+
+    /// 清空内存和磁盘上的所有缓存
+    pub fn clear(&self) -> Result<()> {
+        self.memory.lock().unwrap().clear();
+        if let Some(dir) = &self.dir {
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 缓存文件名使用键的 SHA-256，避免搜索关键字中的特殊字符和过长的文件名
+    fn file_path(&self, kind: CacheKind, key: &str) -> Option<PathBuf> {
+        let name: String = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(kind.name()).join(format!("{}.json", name)))
+    }
+
+    fn read_disk(&self, kind: CacheKind, key: &str) -> Option<CacheEntry> {
+        read_entry(&self.file_path(kind, key)?, kind)
+    }
+
+    fn write_disk(&self, kind: CacheKind, key: &str, entry: &CacheEntry) -> Result<()> {
+        if let Some(path) = self.file_path(kind, key) {
+            write_atomic(&path, &serde_json::to_vec(entry)?)?;
+        }
+        Ok(())
+    }
+
+    /// 删除磁盘上已经失效或无法解析的缓存文件
+    fn prune_disk(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        for kind in CacheKind::ALL {
+            let Ok(files) = fs::read_dir(dir.join(kind.name())) else {
+                continue;
+            };
+            // 只检查缓存文件，跳过其他进程正在写入的临时文件
+            let paths = files.flatten().map(|file| file.path());
+            for path in paths.filter(|path| path.extension().is_some_and(|ext| ext == "json")) {
+                read_entry(&path, kind);
+            }
+        }
+    }
+}
+
+/// 读取缓存文件，已经失效或无法解析的文件直接删除
+fn read_entry(path: &Path, kind: CacheKind) -> Option<CacheEntry> {
+    let bytes = fs::read(path).ok()?;
+    match serde_json::from_slice::<CacheEntry>(&bytes) {
+        Ok(entry) if !entry.is_obsolete(kind) => Some(entry),
+        _ => {
+            let _ = fs::remove_file(path);
+            None
+        }
+    }
+}
+
+/// 带缓存的书源
+///
+/// 搜索、详情和目录优先使用缓存，跳过缓存时仍会用新结果刷新缓存；所有节点都不可用时
+/// 退回到过期的缓存。启用 [`ChapterStore`] 后章节正文只请求一次。
+pub struct CachedSource {
+    inner: Arc<dyn BookSource>,
+    cache: ResponseCache,
+    chapters: Option<ChapterStore>,
+    bypass: bool,
+}
+
+impl CachedSource {
+    pub fn new(inner: Arc<dyn BookSource>, cache: ResponseCache) -> Self {
+        Self {
+            inner,
+            cache,
+            chapters: None,
+            bypass: false,
+        }
+    }
+
+    /// 使用应用共享的 API 客户端、缓存和章节存储
+    #[cfg(feature = "gui")]
+    pub fn for_app(app_handle: &AppHandle) -> Self {
+        Self::new(
+            FanqieApi::for_app(app_handle),
+            app_handle.state::<ResponseCache>().inner().clone(),
+        )
+        .with_chapters(app_handle.state::<ChapterStore>().inner().clone())
+    }
+
+    /// 章节正文先从 `store` 读取，新获取的章节写入 `store`
+    pub fn with_chapters(mut self, store: ChapterStore) -> Self {
+        self.chapters = Some(store);
+        self
+    }
+
+    /// 为 true 时忽略已有缓存，总是重新请求
+    pub fn bypass(mut self, bypass: bool) -> Self {
+        self.bypass = bypass;
+        self
+    }
+
+    async fn cached<T, Fut>(&self, kind: CacheKind, key: &str, fetch: Fut) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        Fut: Future<Output = Result<T>>,
+    {
+        if !self.bypass {
+            if let Some(value) = self.cache.get(kind, key) {
+                return Ok(value);
+            }
+        }
+        match fetch.await {
+            Ok(value) => {
+                self.cache.put(kind, key, &value);
+                Ok(value)
+            }
+            // 书籍下架等业务错误照常返回，只有节点都不可用时才使用过期数据
+            Err(e) if e.is::<FallbackError>() => self.cache.get_stale(kind, key).ok_or(e),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save_chapter(&self, store: &ChapterStore, item_id: &str, content: &str) {
+        if let Err(e) = store.put(item_id, content) {
+            eprintln!("保存章节 {} 失败: {}", item_id, e);
+        }
+    }
+}
+
+#[async_trait]
+impl BookSource for CachedSource {
+    async fn search_books(&self, keyword: &str, offset: i32) -> Result<SearchResult> {
+        let key = format!("{}\n{}", offset, keyword);
+        self.cached(
+            CacheKind::Search,
+            &key,
+            self.inner.search_books(keyword, offset),
+        )
+        .await
+    }
+
+    async fn get_book_detail(&self, book_id: &str) -> Result<BookInfo> {
+        self.cached(
+            CacheKind::Detail,
+            book_id,
+            self.inner.get_book_detail(book_id),
+        )
+        .await
+    }
+
+    async fn get_directory(&self, book_id: &str) -> Result<Vec<Chapter>> {
+        self.cached(
+            CacheKind::Directory,
+            book_id,
+            self.inner.get_directory(book_id),
+        )
+        .await
+    }
+
+    async fn get_chapter_content(&self, item_id: &str) -> Result<String> {
+        let Some(store) = &self.chapters else {
+            return self.inner.get_chapter_content(item_id).await;
+        };
+        if let Some(content) = store.get(item_id) {
+            return Ok(content);
+        }
+        let content = self.inner.get_chapter_content(item_id).await?;
+        self.save_chapter(store, item_id, &content);
+        Ok(content)
+    }
+
+    async fn get_cover(&self, url: &str) -> Result<Vec<u8>> {
+        self.inner.get_cover(url).await
+    }
+
+    async fn get_full_content(&self, book_id: &str) -> Result<HashMap<String, String>> {
+        let Some(store) = &self.chapters else {
+            return self.inner.get_full_content(book_id).await;
+        };
+        let chapters = self.get_directory(book_id).await.unwrap_or_default();
+        let mut content_map: HashMap<String, String> = chapters
+            .iter()
+            .filter_map(|ch| store.get(&ch.id).map(|content| (ch.id.clone(), content)))
+            .collect();
+        if !chapters.is_empty() && content_map.len() == chapters.len() {
+            return Ok(content_map);
+        }
+
+        match self.inner.get_full_content(book_id).await {
+            Ok(fetched) => {
+                for (item_id, content) in fetched {
+                    self.save_chapter(store, &item_id, &content);
+                    content_map.insert(item_id, content);
+                }
+                Ok(content_map)
+            }
+            // 批量接口不可用时先返回已保存的章节，其余由下载器逐章获取
+            Err(_) if !content_map.is_empty() => Ok(content_map),
+            Err(e) => Err(e),
+        }
+    }
+}
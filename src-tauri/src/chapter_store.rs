@@ -0,0 +1,60 @@
+use crate::paths::{sanitize_file_name, write_atomic};
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// 章节正文存储
+///
+/// 已发布的章节内容不再变化，按章节 id 压缩保存为 `<root>/<id 末两位>/<章节 id>.txt.gz`。
+/// 所有书籍的下载和导出共用，已保存的章节不会再次请求。封面图片保存为
+/// `<root>/covers/<book_id>`，离线导出 EPUB 时使用。
+#[derive(Debug, Clone)]
+pub struct ChapterStore {
+    root: PathBuf,
+}
+
+impl ChapterStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, item_id: &str) -> PathBuf {
+        let name = sanitize_file_name(item_id);
+        // 按 id 末两位分目录，避免单个目录下文件过多
+        let shard: String = name.chars().rev().take(2).collect();
+        self.root.join(shard).join(format!("{}.txt.gz", name))
+    }
+
+    fn cover_path(&self, book_id: &str) -> PathBuf {
+        self.root.join("covers").join(sanitize_file_name(book_id))
+    }
+
+    /// 读取已保存的章节，文件不存在或损坏时返回 `None`
+    pub fn get(&self, item_id: &str) -> Option<String> {
+        let file = File::open(self.path(item_id)).ok()?;
+        let mut content = String::new();
+        GzDecoder::new(file).read_to_string(&mut content).ok()?;
+        Some(content)
+    }
+
+    /// 保存章节正文
+    pub fn put(&self, item_id: &str, content: &str) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes())?;
+        write_atomic(&self.path(item_id), &encoder.finish()?)
+    }
+
+    /// 读取已保存的封面图片
+    pub fn cover(&self, book_id: &str) -> Option<Vec<u8>> {
+        fs::read(self.cover_path(book_id)).ok()
+    }
+
+    /// 保存封面图片
+    pub fn put_cover(&self, book_id: &str, data: &[u8]) -> Result<()> {
+        write_atomic(&self.cover_path(book_id), data)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::paths::{sanitize_file_name, write_atomic};
+use crate::types::ChapterContent;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// 章节断点存储
+///
+/// 每本书对应 `<root>/<book_id>/` 目录，每个已下载的章节保存为 `<章节 id>.json`，
+/// 中断后重新下载时可跳过已获取的章节。文件生成后断点即被删除。
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    root: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn book_dir(&self, book_id: &str) -> PathBuf {
+        self.root.join(sanitize_file_name(book_id))
+    }
+
+    /// 读取某本书已保存的全部章节，key 为章节 id
+    pub fn load(&self, book_id: &str) -> Result<HashMap<String, ChapterContent>> {
+        let dir = self.book_dir(book_id);
+        let mut chapters = HashMap::new();
+        if !dir.exists() {
+            return Ok(chapters);
+        }
+
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(chapter_id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            // 写入中断留下的损坏文件直接忽略，之后会重新下载
+            match fs::read(&path).map(|bytes| serde_json::from_slice::<ChapterContent>(&bytes)) {
+                Ok(Ok(content)) => {
+                    chapters.insert(chapter_id.to_string(), content);
+                }
+                _ => eprintln!("忽略损坏的断点文件: {}", path.display()),
+            }
+        }
+
+        Ok(chapters)
+    }
+
+    /// 保存单个章节
+    pub fn save(&self, book_id: &str, chapter_id: &str, content: &ChapterContent) -> Result<()> {
+        let path = self
+            .book_dir(book_id)
+            .join(format!("{}.json", sanitize_file_name(chapter_id)));
+        write_atomic(&path, &serde_json::to_vec(content)?)
+    }
+
+    /// 删除某本书的全部断点
+    pub fn clear(&self, book_id: &str) -> Result<()> {
+        let dir = self.book_dir(book_id);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
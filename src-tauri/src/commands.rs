@@ -1,33 +1,58 @@
 use crate::api::FanqieApi;
+use crate::book_source::{self, BookSource};
+use crate::cache::{CachedSource, ResponseCache};
+use crate::checkpoint::CheckpointStore;
+use crate::control::DownloadRegistry;
 use crate::downloader::Downloader;
+use crate::error::AppError;
+use crate::health::SourceHealth;
+use crate::library::{unix_now, FollowedBook, Library, LibraryEntry};
+use crate::network::{ConnectionTest, NetworkSettings};
+use crate::paths::app_data_path;
+use crate::queue::{DownloadQueue, QueueItem};
+use crate::sources::{ApiSource, SourceStore};
 use crate::types::*;
-use tauri::AppHandle;
+use crate::updater::{check_followed, BookUpdate};
+use tauri::{AppHandle, State};
 
-/// 搜索书籍
+/// 搜索书籍，`refresh` 为 true 时跳过缓存
 #[tauri::command]
-pub async fn search_books(keyword: String, offset: i32) -> Result<SearchResult, String> {
-    let api = FanqieApi::new();
-    api.search_books(&keyword, offset)
+pub async fn search_books(
+    keyword: String,
+    offset: i32,
+    refresh: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<SearchResult, AppError> {
+    let source = CachedSource::for_app(&app_handle).bypass(refresh.unwrap_or(false));
+    source.search_books(&keyword, offset)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
-/// 获取书籍详情
+/// 获取书籍详情，`refresh` 为 true 时跳过缓存
 #[tauri::command]
-pub async fn get_book_detail(book_id: String) -> Result<BookInfo, String> {
-    let api = FanqieApi::new();
-    api.get_book_detail(&book_id)
+pub async fn get_book_detail(
+    book_id: String,
+    refresh: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<BookInfo, AppError> {
+    let source = CachedSource::for_app(&app_handle).bypass(refresh.unwrap_or(false));
+    source.get_book_detail(&book_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
-/// 获取章节列表
+/// 获取章节列表，`refresh` 为 true 时跳过缓存
 #[tauri::command]
-pub async fn get_chapters(book_id: String) -> Result<Vec<Chapter>, String> {
-    let api = FanqieApi::new();
-    api.get_directory(&book_id)
+pub async fn get_chapters(
+    book_id: String,
+    refresh: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<Vec<Chapter>, AppError> {
+    let source = CachedSource::for_app(&app_handle).bypass(refresh.unwrap_or(false));
+    source.get_directory(&book_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// 下载书籍
@@ -35,36 +60,311 @@ pub async fn get_chapters(book_id: String) -> Result<Vec<Chapter>, String> {
 pub async fn download_book(
     options: DownloadOptions,
     app_handle: AppHandle,
-) -> Result<DownloadResult, String> {
-    let downloader = Downloader::new();
-    downloader
-        .download(options, app_handle)
+    registry: State<'_, DownloadRegistry>,
+) -> Result<DownloadResult, AppError> {
+    run_download(options, app_handle, &registry).await
+}
+
+/// 以可取消的方式执行下载
+async fn run_download(
+    options: DownloadOptions,
+    app_handle: AppHandle,
+    registry: &DownloadRegistry,
+) -> Result<DownloadResult, AppError> {
+    let downloader = Downloader::for_app(&app_handle)?;
+    let book_id = options.book_id.clone();
+    let control = registry.register(&book_id)?;
+    let result = downloader
+        .with_control(control.clone())
+        .download(options, &app_handle)
+        .await;
+    registry.release(&book_id, &control);
+    result.map_err(AppError::from)
+}
+
+/// 增量更新已下载的书籍
+#[tauri::command]
+pub async fn update_book(
+    book_id: String,
+    app_handle: AppHandle,
+    registry: State<'_, DownloadRegistry>,
+) -> Result<UpdateResult, AppError> {
+    let downloader = Downloader::fresh_for_app(&app_handle)?;
+    let control = registry.register(&book_id)?;
+    let result = downloader
+        .with_control(control.clone())
+        .update(&book_id, &app_handle)
+        .await;
+    registry.release(&book_id, &control);
+    result.map_err(AppError::from)
+}
+
+/// 取消下载
+#[tauri::command]
+pub fn cancel_download(book_id: String, registry: State<'_, DownloadRegistry>) -> Result<(), AppError> {
+    registry
+        .get(&book_id)
+        .map(|control| control.cancel())
+        .map_err(AppError::from)
+}
+
+/// 暂停下载
+#[tauri::command]
+pub fn pause_download(book_id: String, registry: State<'_, DownloadRegistry>) -> Result<(), AppError> {
+    registry
+        .get(&book_id)
+        .map(|control| control.pause())
+        .map_err(AppError::from)
+}
+
+/// 恢复下载
+#[tauri::command]
+pub fn resume_download(book_id: String, registry: State<'_, DownloadRegistry>) -> Result<(), AppError> {
+    registry
+        .get(&book_id)
+        .map(|control| control.resume())
+        .map_err(AppError::from)
+}
+
+/// 加入下载队列
+#[tauri::command]
+pub fn enqueue_download(options: DownloadOptions, queue: State<'_, DownloadQueue>) -> Result<(), AppError> {
+    queue.enqueue(options).map_err(AppError::from)
+}
+
+/// 获取下载队列
+#[tauri::command]
+pub fn list_queue(queue: State<'_, DownloadQueue>) -> Vec<QueueItem> {
+    queue.list()
+}
+
+/// 调整下载队列顺序
+#[tauri::command]
+pub fn reorder_queue(book_ids: Vec<String>, queue: State<'_, DownloadQueue>) {
+    queue.reorder(&book_ids)
+}
+
+/// 从下载队列移除
+#[tauri::command]
+pub fn remove_from_queue(book_id: String, queue: State<'_, DownloadQueue>) -> Result<(), AppError> {
+    queue.remove(&book_id).map_err(AppError::from)
+}
+
+/// 设置队列同时下载数
+#[tauri::command]
+pub fn set_queue_concurrency(max_concurrent: usize, queue: State<'_, DownloadQueue>) {
+    queue.set_max_concurrent(max_concurrent)
+}
+
+/// 列出书库，可按书名或作者筛选
+#[tauri::command]
+pub fn list_library(keyword: Option<String>, library: State<'_, Library>) -> Result<Vec<LibraryEntry>, AppError> {
+    library.list(keyword.as_deref()).map_err(AppError::from)
+}
+
+/// 获取书库中的一本书
+#[tauri::command]
+pub fn get_library_entry(book_id: String, library: State<'_, Library>) -> Result<Option<LibraryEntry>, AppError> {
+    library.get(&book_id).map_err(AppError::from)
+}
+
+/// 从书库移除，同时清理断点，`delete_file` 为 true 时一并删除生成的文件
+#[tauri::command]
+pub fn remove_library_entry(
+    book_id: String,
+    delete_file: bool,
+    app_handle: AppHandle,
+    library: State<'_, Library>,
+) -> Result<(), AppError> {
+    let entry = library.get(&book_id)?;
+    library.remove(&book_id)?;
+
+    let checkpoint_dir = app_data_path(&app_handle, "checkpoints")?;
+    CheckpointStore::new(checkpoint_dir)
+        .clear(&book_id)
+        ?;
+
+    if let Some(entry) = entry.filter(|_| delete_file) {
+        match std::fs::remove_file(&entry.file_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 不联网，用本地保存的章节把书库中的书籍导出为指定格式
+#[tauri::command]
+pub async fn export_book(
+    book_id: String,
+    format: String,
+    save_path: String,
+    options: Option<ExportOptions>,
+    app_handle: AppHandle,
+) -> Result<DownloadResult, AppError> {
+    let downloader = Downloader::for_app(&app_handle)?;
+    // 读取章节、打包和落盘都是同步 IO，放到阻塞线程池执行
+    tokio::task::spawn_blocking(move || {
+        downloader.export(&book_id, &format, &save_path, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| AppError::Other(e.to_string()))?
+    .map_err(AppError::from)
+}
+
+/// 关注书籍，记录当前章节数和状态作为之后检查更新的基准
+#[tauri::command]
+pub async fn follow_book(
+    book_id: String,
+    auto_download: bool,
+    app_handle: AppHandle,
+    library: State<'_, Library>,
+) -> Result<FollowedBook, AppError> {
+    let source = book_source::fresh_for_app(&app_handle);
+    let info = source.get_book_detail(&book_id).await?;
+    let chapters = source.get_directory(&book_id).await?;
+
+    let now = unix_now();
+    let book = FollowedBook {
+        book_id,
+        book_name: info.book_name,
+        chapter_count: chapters.len(),
+        status: info.status,
+        auto_download,
+        followed_at: now,
+        checked_at: now,
+    };
+    library.follow(&book, &chapters)?;
+    Ok(book)
+}
+
+/// 取消关注
+#[tauri::command]
+pub fn unfollow_book(book_id: String, library: State<'_, Library>) -> Result<(), AppError> {
+    library.unfollow(&book_id).map_err(AppError::from)
+}
+
+/// 获取关注的书籍
+#[tauri::command]
+pub fn list_followed(library: State<'_, Library>) -> Result<Vec<FollowedBook>, AppError> {
+    library.followed().map_err(AppError::from)
+}
+
+/// 立即检查关注书籍的更新
+#[tauri::command]
+pub async fn check_updates(app_handle: AppHandle) -> Result<Vec<BookUpdate>, AppError> {
+    check_followed(&app_handle).await.map_err(AppError::from)
+}
+
+/// 获取 API 节点列表
+#[tauri::command]
+pub fn get_api_sources(sources: State<'_, SourceStore>) -> Vec<ApiSource> {
+    sources.list()
+}
+
+/// 获取 API 节点的健康状况，`probe` 为 true 时先重新探测
+#[tauri::command]
+pub async fn get_source_health(
+    probe: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<Vec<SourceHealth>, AppError> {
+    let api = FanqieApi::for_app(&app_handle);
+    if probe.unwrap_or(false) {
+        Ok(api.probe_sources().await)
+    } else {
+        Ok(api.source_health())
+    }
+}
+
+/// 添加 API 节点
+#[tauri::command]
+pub fn add_api_source(
+    name: String,
+    base_url: String,
+    sources: State<'_, SourceStore>,
+) -> Result<(), AppError> {
+    sources.add(&name, &base_url).map_err(AppError::from)
+}
+
+/// 删除 API 节点
+#[tauri::command]
+pub fn remove_api_source(base_url: String, sources: State<'_, SourceStore>) -> Result<(), AppError> {
+    sources.remove(&base_url).map_err(AppError::from)
+}
+
+/// 启用或停用 API 节点
+#[tauri::command]
+pub fn set_api_source_enabled(
+    base_url: String,
+    enabled: bool,
+    sources: State<'_, SourceStore>,
+) -> Result<(), AppError> {
+    sources
+        .set_enabled(&base_url, enabled)
+        .map_err(AppError::from)
+}
+
+/// 设置 API 节点的每秒请求数，不传时恢复默认限速
+#[tauri::command]
+pub fn set_api_source_rate_limit(
+    base_url: String,
+    rate_limit: Option<f64>,
+    sources: State<'_, SourceStore>,
+) -> Result<(), AppError> {
+    sources
+        .set_rate_limit(&base_url, rate_limit)
+        .map_err(AppError::from)
+}
+
+/// 设置 API 节点单独使用的代理，不传时使用全局代理
+#[tauri::command]
+pub fn set_api_source_proxy(
+    base_url: String,
+    proxy: Option<String>,
+    sources: State<'_, SourceStore>,
+) -> Result<(), AppError> {
+    sources.set_proxy(&base_url, proxy).map_err(AppError::from)
+}
+
+/// 调整 API 节点的优先级
+#[tauri::command]
+pub fn reorder_api_sources(
+    base_urls: Vec<String>,
+    sources: State<'_, SourceStore>,
+) -> Result<(), AppError> {
+    sources.reorder(&base_urls).map_err(AppError::from)
+}
+
+/// 获取网络设置
+#[tauri::command]
+pub fn get_network_settings(app_handle: AppHandle) -> NetworkSettings {
+    FanqieApi::for_app(&app_handle).network_settings()
+}
+
+/// 保存网络设置，之后的请求立即使用新设置
+#[tauri::command]
+pub fn set_network_settings(settings: NetworkSettings, app_handle: AppHandle) -> Result<(), AppError> {
+    FanqieApi::for_app(&app_handle)
+        .set_network_settings(settings)
+        .map_err(AppError::from)
+}
+
+/// 测试节点连通性，`settings` 为空时使用当前设置，`base_url` 为空时测试所有已启用节点
+#[tauri::command]
+pub async fn test_connection(
+    settings: Option<NetworkSettings>,
+    base_url: Option<String>,
+    app_handle: AppHandle,
+) -> Result<Vec<ConnectionTest>, AppError> {
+    let api = FanqieApi::for_app(&app_handle);
+    let settings = settings.unwrap_or_else(|| api.network_settings());
+    api.test_connection(&settings, base_url.as_deref())
         .await
-        .map_err(|e| e.to_string())
-}
-
-/// 获取可用的 API 节点列表
-#[tauri::command]
-pub fn get_api_sources() -> Vec<ApiSource> {
-    vec![
-        ApiSource {
-            name: "中国|浙江省|宁波市|电信".to_string(),
-            base_url: "http://qkfqapi.vv9v.cn".to_string(),
-        },
-        ApiSource {
-            name: "中国|北京市|腾讯云".to_string(),
-            base_url: "http://49.232.137.12".to_string(),
-        },
-        ApiSource {
-            name: "日本|东京".to_string(),
-            base_url: "https://fq.shusan.cn".to_string(),
-        },
-    ]
-}
-
-/// API 节点信息
-#[derive(serde::Serialize)]
-pub struct ApiSource {
-    pub name: String,
-    pub base_url: String,
+        .map_err(AppError::from)
+}
+
+/// 清空搜索、详情和目录缓存
+#[tauri::command]
+pub fn clear_cache(cache: State<'_, ResponseCache>) -> Result<(), AppError> {
+    cache.clear().map_err(AppError::from)
 }
@@ -0,0 +1,111 @@
+use crate::error::AppError;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 下载被用户取消
+#[derive(Debug, thiserror::Error)]
+#[error("下载已取消")]
+pub struct Cancelled;
+
+/// 单个下载任务的控制句柄
+pub struct DownloadControl {
+    cancelled: watch::Sender<bool>,
+    paused: watch::Sender<bool>,
+}
+
+impl DownloadControl {
+    pub fn new() -> Self {
+        Self {
+            cancelled: watch::Sender::new(false),
+            paused: watch::Sender::new(false),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+        // 唤醒处于暂停等待中的任务，让它们尽快退出
+        self.paused.send_replace(false);
+    }
+
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// 等待 `duration`，期间取消时立即返回 [`Cancelled`]
+    pub async fn sleep(&self, duration: Duration) -> Result<()> {
+        let mut cancelled = self.cancelled.subscribe();
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => Ok(()),
+            _ = cancelled.wait_for(|cancelled| *cancelled) => Err(Cancelled.into()),
+        }
+    }
+
+    /// 在章节请求之间调用：暂停时等待恢复，已取消时返回 [`Cancelled`]
+    pub async fn proceed(&self) -> Result<()> {
+        let mut paused = self.paused.subscribe();
+        loop {
+            if self.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            if !*paused.borrow_and_update() {
+                return Ok(());
+            }
+            if paused.changed().await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Default for DownloadControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 正在进行的下载任务，按 book_id 索引
+#[derive(Default)]
+pub struct DownloadRegistry {
+    tasks: Mutex<HashMap<String, Arc<DownloadControl>>>,
+}
+
+impl DownloadRegistry {
+    /// 登记新的下载任务，同一本书不允许同时下载
+    pub fn register(&self, book_id: &str) -> Result<Arc<DownloadControl>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.contains_key(book_id) {
+            return Err(AppError::already_exists("该书籍正在下载中").into());
+        }
+        let control = Arc::new(DownloadControl::new());
+        tasks.insert(book_id.to_string(), control.clone());
+        Ok(control)
+    }
+
+    /// 任务结束时注销，已被同一本书的新任务取代时保留新任务的登记
+    pub fn release(&self, book_id: &str, control: &Arc<DownloadControl>) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.get(book_id).is_some_and(|task| Arc::ptr_eq(task, control)) {
+            tasks.remove(book_id);
+        }
+    }
+
+    pub fn get(&self, book_id: &str) -> Result<Arc<DownloadControl>> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(book_id)
+            .cloned()
+            .ok_or_else(|| AppError::not_found("没有正在进行的下载任务").into())
+    }
+}
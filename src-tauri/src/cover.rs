@@ -0,0 +1,146 @@
+use crate::types::BookInfo;
+use anyhow::{anyhow, Result};
+use image::ImageFormat;
+use std::io::Cursor;
+
+/// 标题卡片封面的尺寸，与常见的小说封面比例一致
+const CARD_WIDTH: u32 = 600;
+const CARD_HEIGHT: u32 = 800;
+/// 书名每行最多显示的字数
+const TITLE_LINE_CHARS: usize = 8;
+/// 卡片边框到边缘的距离和边框宽度
+const FRAME_INSET: u32 = 30;
+const FRAME_WIDTH: u32 = 3;
+/// 卡片背景色，按书名选取，让不同的书容易区分
+const CARD_COLORS: [[u8; 3]; 4] = [
+    [0xc0, 0x39, 0x2b],
+    [0x2c, 0x3e, 0x50],
+    [0x27, 0xae, 0x60],
+    [0x8e, 0x44, 0xad],
+];
+const TEXT_COLOR: [u8; 3] = [0xfd, 0xf6, 0xe3];
+
+/// EPUB 封面图片
+#[derive(Debug, Clone)]
+pub struct Cover {
+    pub data: Vec<u8>,
+    pub mime_type: &'static str,
+    /// 在 EPUB 中的文件名
+    pub file_name: &'static str,
+}
+
+impl Cover {
+    /// 使用下载的封面图片，阅读器普遍不支持的格式（如 WebP）转换为 JPEG
+    pub fn from_image(data: Vec<u8>) -> Result<Self> {
+        let format = image::guess_format(&data).map_err(|e| anyhow!("无法识别封面格式: {}", e))?;
+        match format {
+            ImageFormat::Jpeg => Ok(Self::jpeg(data)),
+            ImageFormat::Png => Ok(Self::png(data)),
+            _ => {
+                let image = image::load_from_memory_with_format(&data, format)
+                    .map_err(|e| anyhow!("封面图片解码失败: {}", e))?;
+                let mut jpeg = Vec::new();
+                // JPEG 不支持透明通道，先转为 RGB
+                image
+                    .to_rgb8()
+                    .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+                    .map_err(|e| anyhow!("封面图片转换失败: {}", e))?;
+                Ok(Self::jpeg(jpeg))
+            }
+        }
+    }
+
+    fn jpeg(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            mime_type: "image/jpeg",
+            file_name: "cover.jpg",
+        }
+    }
+
+    fn png(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            mime_type: "image/png",
+            file_name: "cover.png",
+        }
+    }
+
+    /// 没有可用的封面图片时生成的显示书名和作者的 SVG 卡片
+    pub fn title_card(book_info: &BookInfo) -> Self {
+        Self {
+            data: card_svg(book_info, card_color(book_info)),
+            mime_type: "image/svg+xml",
+            file_name: "cover.svg",
+        }
+    }
+}
+
+/// 按书名选取卡片背景色，同一本书总是相同
+fn card_color(book_info: &BookInfo) -> [u8; 3] {
+    let sum = book_info
+        .book_name
+        .chars()
+        .map(|c| c as usize)
+        .sum::<usize>();
+    CARD_COLORS[sum % CARD_COLORS.len()]
+}
+
+/// 显示书名和作者的 SVG 卡片
+fn card_svg(book_info: &BookInfo, color: [u8; 3]) -> Vec<u8> {
+    let chars: Vec<char> = book_info.book_name.chars().collect();
+    let lines: Vec<String> = chars
+        .chunks(TITLE_LINE_CHARS)
+        .take(4)
+        .map(|line| escape_xml(&line.iter().collect::<String>()))
+        .collect();
+    let line_height = 72;
+    let top = CARD_HEIGHT / 2 - (lines.len() as u32 * line_height) / 2;
+    let title: String = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            format!(
+                r#"<text x="50%" y="{}" font-size="56" font-weight="bold" fill="{}" text-anchor="middle">{}</text>"#,
+                top + i as u32 * line_height,
+                hex(TEXT_COLOR),
+                line
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let svg = format!(
+        r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">
+<rect width="100%" height="100%" fill="{bg}"/>
+<rect x="{inset}" y="{inset}" width="{iw}" height="{ih}" fill="none" stroke="{fg}" stroke-width="{frame}"/>
+{title}
+<text x="50%" y="{author_y}" font-size="32" fill="{fg}" text-anchor="middle">{author}</text>
+</svg>"##,
+        w = CARD_WIDTH,
+        h = CARD_HEIGHT,
+        bg = hex(color),
+        fg = hex(TEXT_COLOR),
+        inset = FRAME_INSET,
+        frame = FRAME_WIDTH,
+        iw = CARD_WIDTH - 2 * FRAME_INSET,
+        ih = CARD_HEIGHT - 2 * FRAME_INSET,
+        title = title,
+        author_y = CARD_HEIGHT - 120,
+        author = escape_xml(&book_info.author),
+    );
+    svg.into_bytes()
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// 转义 XML 文本中的特殊字符
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
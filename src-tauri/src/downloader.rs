@@ -1,68 +1,222 @@
 use crate::api::FanqieApi;
+#[cfg(feature = "gui")]
+use crate::book_source;
+use crate::book_source::BookSource;
+use crate::chapter_store::ChapterStore;
+use crate::checkpoint::CheckpointStore;
+use crate::control::{Cancelled, DownloadControl};
+use crate::cover::{escape_xml, Cover};
+use crate::error::AppError;
+use crate::library::Library;
+#[cfg(feature = "gui")]
+use crate::paths::app_data_path;
+use crate::progress::ProgressSink;
 use crate::types::*;
 use anyhow::{anyhow, Result};
-use epub_builder::{EpubBuilder, EpubContent, ZipLibrary};
-use std::fs::File;
+use epub_builder::{EpubBuilder, EpubContent, ReferenceType, ZipLibrary};
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::Path;
-use tauri::{AppHandle, Emitter};
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager};
+
+/// 普通模式默认并发数
+const DEFAULT_CONCURRENCY: usize = 4;
+/// 普通模式最大并发数
+const MAX_CONCURRENCY: usize = 16;
+/// 失败章节默认重试轮数
+const DEFAULT_RETRY_ROUNDS: u32 = 2;
+/// 失败章节最大重试轮数，最后一轮前等待约半分钟
+const MAX_RETRY_ROUNDS: u32 = 5;
+/// 首轮重试前的等待时间，之后每轮加倍
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+/// 可以生成的文件格式
+const SUPPORTED_FORMATS: [&str; 2] = ["txt", "epub"];
+/// 缺失章节的占位文本，具体错误只记录在 `failed_chapters` 中
+const MISSING_CHAPTER_TEXT: &str = "（本章下载失败，内容缺失）";
+
+/// 单次下载任务的上下文
+struct TaskContext<'a> {
+    book_id: &'a str,
+    progress: &'a dyn ProgressSink,
+    concurrency: usize,
+    retry_rounds: u32,
+}
+
+impl<'a> TaskContext<'a> {
+    fn new(options: &'a DownloadOptions, progress: &'a dyn ProgressSink) -> Self {
+        Self {
+            book_id: &options.book_id,
+            progress,
+            concurrency: options
+                .concurrency
+                .unwrap_or(DEFAULT_CONCURRENCY)
+                .clamp(1, MAX_CONCURRENCY),
+            retry_rounds: options
+                .retry_rounds
+                .unwrap_or(DEFAULT_RETRY_ROUNDS)
+                .min(MAX_RETRY_ROUNDS),
+        }
+    }
+
+    /// 发送进度
+    fn emit_progress(&self, current: usize, total: usize, message: &str) {
+        let percent = if total > 0 {
+            (current as f64 / total as f64) * 100.0
+        } else {
+            0.0
+        };
+        self.progress.report(DownloadProgress {
+            current,
+            total,
+            percent,
+            message: message.to_string(),
+            book_id: self.book_id.to_string(),
+        });
+    }
+}
 
 /// 下载器
 pub struct Downloader {
-    api: FanqieApi,
+    source: Arc<dyn BookSource>,
+    checkpoints: Option<CheckpointStore>,
+    chapters: Option<ChapterStore>,
+    library: Option<Library>,
+    control: Option<Arc<DownloadControl>>,
 }
 
 impl Downloader {
     pub fn new() -> Self {
+        Self::with_source(Arc::new(FanqieApi::new()))
+    }
+
+    /// 使用指定的书源
+    pub fn with_source(source: Arc<dyn BookSource>) -> Self {
         Self {
-            api: FanqieApi::new(),
+            source,
+            checkpoints: None,
+            chapters: None,
+            library: None,
+            control: None,
         }
     }
 
-    pub fn with_api(api: FanqieApi) -> Self {
-        Self { api }
+    /// 使用应用的 API 配置、应用数据目录下的断点存储和应用书库
+    #[cfg(feature = "gui")]
+    pub fn for_app(app_handle: &AppHandle) -> Result<Self> {
+        Self::for_app_with_source(app_handle, book_source::for_app(app_handle))
+    }
+
+    /// 与 [`Downloader::for_app`] 相同，但跳过响应缓存，增量更新需要最新的目录
+    #[cfg(feature = "gui")]
+    pub fn fresh_for_app(app_handle: &AppHandle) -> Result<Self> {
+        Self::for_app_with_source(app_handle, book_source::fresh_for_app(app_handle))
     }
 
-    /// 下载书籍
+    #[cfg(feature = "gui")]
+    fn for_app_with_source(app_handle: &AppHandle, source: Arc<dyn BookSource>) -> Result<Self> {
+        Ok(Self::with_source(source)
+            .with_checkpoints(CheckpointStore::new(app_data_path(app_handle, "checkpoints")?))
+            .with_chapters(app_handle.state::<ChapterStore>().inner().clone())
+            .with_library(app_handle.state::<Library>().inner().clone()))
+    }
+
+    /// 启用断点续传，已下载的章节会持久化到 `store`
+    pub fn with_checkpoints(mut self, store: CheckpointStore) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    /// 下载时把封面保存到 `store`，离线导出时从 `store` 读取章节正文和封面
+    pub fn with_chapters(mut self, store: ChapterStore) -> Self {
+        self.chapters = Some(store);
+        self
+    }
+
+    /// 下载完成后把书籍及章节清单记录到书库，供增量更新使用
+    pub fn with_library(mut self, library: Library) -> Self {
+        self.library = Some(library);
+        self
+    }
+
+    /// 绑定控制句柄，用于取消或暂停下载
+    pub fn with_control(mut self, control: Arc<DownloadControl>) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// 暂停时等待恢复，已取消时返回 [`Cancelled`]
+    async fn proceed(&self) -> Result<()> {
+        match &self.control {
+            Some(control) => control.proceed().await,
+            None => Ok(()),
+        }
+    }
+
+    /// 等待 `delay`，下载被取消时提前结束
+    async fn sleep(&self, delay: Duration) -> Result<()> {
+        match &self.control {
+            Some(control) => control.sleep(delay).await,
+            None => {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// 下载书籍，进度通过 `progress` 汇报
     pub async fn download(
         &self,
         options: DownloadOptions,
-        app_handle: AppHandle,
+        progress: &dyn ProgressSink,
     ) -> Result<DownloadResult> {
+        let ctx = TaskContext::new(&options, progress);
         let book_id = &options.book_id;
-        let save_path = &options.save_path;
         let format = options.format.to_lowercase();
+        // 先检查格式，避免下载完整本书后才发现无法生成文件
+        if !SUPPORTED_FORMATS.contains(&format.as_str()) {
+            return Err(unsupported_format(&format).into());
+        }
 
-        // 发送进度
-        let emit_progress = |current: usize, total: usize, message: &str| {
-            let percent = if total > 0 {
-                (current as f64 / total as f64) * 100.0
-            } else {
-                0.0
-            };
-            let _ = app_handle.emit(
-                "download-progress",
-                DownloadProgress {
-                    current,
-                    total,
-                    percent,
-                    message: message.to_string(),
-                    book_id: book_id.clone(),
-                },
-            );
-        };
-
-        emit_progress(0, 100, "正在获取书籍信息...");
+        ctx.emit_progress(0, 100, "正在获取书籍信息...");
 
         // 获取书籍详情
-        let book_info = self.api.get_book_detail(book_id).await?;
-        emit_progress(5, 100, &format!("获取到: {}", book_info.book_name));
+        let book_info = self.source.get_book_detail(book_id).await?;
+        ctx.emit_progress(5, 100, &format!("获取到: {}", book_info.book_name));
+
+        // 之后的步骤都可能被取消，取消时返回带有书名的结果而不是错误
+        let result: Result<DownloadResult> = async {
+            // 获取章节目录
+            ctx.emit_progress(10, 100, "正在获取章节目录...");
+            let chapters = self.source.get_directory(book_id).await?;
+            self.download_directory(&options, &book_info, chapters, &ctx).await
+        }
+        .await;
+
+        match result {
+            Err(e) if e.is::<Cancelled>() => Ok(cancelled_result(&e, book_info.book_name)),
+            result => result,
+        }
+    }
 
-        // 获取章节目录
-        emit_progress(10, 100, "正在获取章节目录...");
-        let chapters = self.api.get_directory(book_id).await?;
+    /// 按已获取的书籍详情和章节目录下载并生成文件
+    async fn download_directory(
+        &self,
+        options: &DownloadOptions,
+        book_info: &BookInfo,
+        chapters: Vec<Chapter>,
+        ctx: &TaskContext<'_>,
+    ) -> Result<DownloadResult> {
+        let book_id = &options.book_id;
+        let save_path = &options.save_path;
+        let format = options.format.to_lowercase();
+        let missing_policy = options.missing_chapters.unwrap_or_default();
         let total_chapters = chapters.len();
-        emit_progress(15, 100, &format!("共 {} 章", total_chapters));
+        ctx.emit_progress(15, 100, &format!("共 {} 章", total_chapters));
 
         // 筛选章节范围
         let chapters_to_download: Vec<_> = chapters
@@ -75,103 +229,556 @@ impl Downloader {
             .collect();
 
         if chapters_to_download.is_empty() {
-            return Err(anyhow!("没有可下载的章节"));
+            return Err(AppError::NoChapters.into());
         }
 
-        // 尝试极速模式
-        emit_progress(20, 100, "尝试极速下载模式...");
-        let chapter_contents = match self.api.get_full_content(book_id).await {
-            Ok(content_map) => {
-                emit_progress(50, 100, "极速模式成功，正在处理内容...");
-                let mut contents = Vec::new();
-                for ch in &chapters_to_download {
-                    if let Some(content) = content_map.get(&ch.id) {
-                        contents.push(ChapterContent {
-                            title: ch.title.clone(),
-                            content: content.clone(),
-                            index: ch.index,
-                        });
+        // 读取断点，跳过已下载的章节
+        let mut fetched = self.load_checkpoints(book_id);
+        let pending: Vec<Chapter> = chapters_to_download
+            .iter()
+            .filter(|ch| !fetched.contains_key(&ch.id))
+            .cloned()
+            .collect();
+        if pending.len() < chapters_to_download.len() {
+            ctx.emit_progress(
+                18,
+                100,
+                &format!(
+                    "已恢复 {} 章，剩余 {} 章",
+                    chapters_to_download.len() - pending.len(),
+                    pending.len()
+                ),
+            );
+        }
+
+        let mut failed = Vec::new();
+        if !pending.is_empty() {
+            self.proceed().await?;
+
+            // 尝试极速模式
+            ctx.emit_progress(20, 100, "尝试极速下载模式...");
+            match self.source.get_full_content(book_id).await {
+                Ok(content_map) => {
+                    ctx.emit_progress(50, 100, "极速模式成功，正在处理内容...");
+                    for ch in &pending {
+                        if let Some(content) = content_map.get(&ch.id) {
+                            let content = ChapterContent {
+                                title: ch.title.clone(),
+                                content: content.clone(),
+                                index: ch.index,
+                            };
+                            self.save_checkpoint(book_id, &ch.id, &content);
+                            fetched.insert(ch.id.clone(), content);
+                        }
+                    }
+
+                    // 如果极速模式没有获取到所有章节，用普通模式补齐剩余章节
+                    let remaining: Vec<Chapter> = pending
+                        .into_iter()
+                        .filter(|ch| !fetched.contains_key(&ch.id))
+                        .collect();
+                    if !remaining.is_empty() {
+                        ctx.emit_progress(55, 100, "极速模式内容不完整，切换到普通模式...");
+                        failed = self
+                            .download_chapters_normal(&remaining, &mut fetched, (55, 80), ctx)
+                            .await?;
                     }
                 }
-                
-                // 如果极速模式没有获取到所有章节，回退到普通模式
-                if contents.len() < chapters_to_download.len() {
-                    emit_progress(55, 100, "极速模式内容不完整，切换到普通模式...");
-                    self.download_chapters_normal(&chapters_to_download, &app_handle, book_id).await?
-                } else {
-                    contents
+                Err(_) => {
+                    ctx.emit_progress(25, 100, "极速模式不可用，使用普通模式...");
+                    failed = self
+                        .download_chapters_normal(&pending, &mut fetched, (25, 80), ctx)
+                        .await?;
                 }
             }
-            Err(_) => {
-                emit_progress(25, 100, "极速模式不可用，使用普通模式...");
-                self.download_chapters_normal(&chapters_to_download, &app_handle, book_id).await?
-            }
-        };
+        }
 
-        emit_progress(85, 100, "正在生成文件...");
+        let failed = self.retry_failed(failed, &mut fetched, ctx).await?;
+
+        if !failed.is_empty() && missing_policy == MissingChapterPolicy::Fail {
+            return Ok(DownloadResult {
+                success: false,
+                status: DownloadStatus::Failed,
+                file_path: None,
+                error: Some(format!("{} 个章节下载失败", failed.len())),
+                book_name: book_info.book_name.clone(),
+                failed_chapters: failed,
+            });
+        }
 
-        // 生成文件
-        let file_path = match format.as_str() {
-            "epub" => {
-                self.create_epub(&book_info, &chapter_contents, save_path)?
-            }
-            _ => {
-                self.create_txt(&book_info, &chapter_contents, save_path)?
-            }
+        // 按目录顺序组装，断点中的标题和序号以最新目录为准，缺失章节用占位文本代替
+        let chapter_contents: Vec<ChapterContent> = chapters_to_download
+            .iter()
+            .map(|ch| {
+                let content = fetched
+                    .remove(&ch.id)
+                    .map_or_else(|| MISSING_CHAPTER_TEXT.to_string(), |c| c.content);
+                ChapterContent {
+                    title: ch.title.clone(),
+                    content,
+                    index: ch.index,
+                }
+            })
+            .collect();
+
+        self.proceed().await?;
+        ctx.emit_progress(85, 100, "正在生成文件...");
+
+        // 封面同时保存到本地，之后离线导出 EPUB 时使用
+        let cover = if format == "epub" || self.chapters.is_some() {
+            self.download_cover(book_info).await
+        } else {
+            None
         };
 
-        emit_progress(100, 100, "下载完成！");
+        // 生成文件
+        let file_path =
+            self.write_book(&format, book_info, cover, &chapter_contents, save_path)?;
+
+        // 占位章节同样记录在书库中并标记为缺失，下次更新时重新生成文件补齐
+        self.record_library(
+            book_info,
+            options,
+            &chapters_to_download,
+            &failed,
+            total_chapters,
+        );
+        self.clear_checkpoints(book_id);
+
+        ctx.emit_progress(100, 100, "下载完成！");
 
         Ok(DownloadResult {
             success: true,
+            status: DownloadStatus::Completed,
             file_path: Some(file_path),
             error: None,
-            book_name: book_info.book_name,
+            book_name: book_info.book_name.clone(),
+            failed_chapters: failed,
+        })
+
+    }
+
+    /// 增量更新已下载的书籍
+    ///
+    /// 与书库中记录的章节清单比对，只获取新增章节和上次缺失的章节。TXT 文件直接追加；
+    /// EPUB、新章节插在已有章节之间或需要替换占位章节时重新生成整个文件。
+    pub async fn update(&self, book_id: &str, progress: &dyn ProgressSink) -> Result<UpdateResult> {
+        let library = self
+            .library
+            .as_ref()
+            .ok_or_else(|| anyhow!("未启用书库，无法增量更新"))?;
+        let entry = library
+            .get(book_id)?
+            .ok_or_else(|| AppError::not_found("没有找到该书籍的下载记录，请先下载"))?;
+        let downloaded = library.chapters(book_id)?;
+        let missing = library.missing_chapters(book_id)?;
+
+        let mut options = entry.options;
+        options.end_chapter = None;
+        let ctx = TaskContext::new(&options, progress);
+
+        ctx.emit_progress(0, 100, "正在检查更新...");
+        let book_info = self.source.get_book_detail(book_id).await?;
+        let chapters = self.source.get_directory(book_id).await?;
+        let total_chapters = chapters.len();
+
+        let start = options.start_chapter.unwrap_or(0);
+        let new_chapters: Vec<Chapter> = chapters
+            .iter()
+            .filter(|ch| {
+                ch.index >= start
+                    && (missing.contains(&ch.id) || !downloaded.iter().any(|c| c.id == ch.id))
+            })
+            .cloned()
+            .collect();
+
+        if new_chapters.is_empty() {
+            if let Err(e) = library.refresh(&book_info, total_chapters) {
+                eprintln!("书库记录失败: {}", e);
+            }
+            ctx.emit_progress(100, 100, "没有新章节");
+            return Ok(UpdateResult {
+                new_chapters: 0,
+                result: DownloadResult {
+                    success: true,
+                    status: DownloadStatus::Completed,
+                    file_path: Some(options.save_path),
+                    error: None,
+                    book_name: book_info.book_name,
+                    failed_chapters: Vec::new(),
+                },
+            });
+        }
+
+        let new_count = new_chapters.len();
+        ctx.emit_progress(10, 100, &format!("发现 {} 个新章节", new_count));
+
+        let last_index = downloaded.last().map(|c| c.index);
+        let appendable = options.format.eq_ignore_ascii_case("txt")
+            && Path::new(&options.save_path).exists()
+            && new_chapters
+                .iter()
+                .all(|ch| Some(ch.index) > last_index && !missing.contains(&ch.id));
+        if !appendable {
+            // 重新生成整个文件，沿用刚获取的详情和目录
+            let result = match self
+                .download_directory(&options, &book_info, chapters, &ctx)
+                .await
+            {
+                Err(e) if e.is::<Cancelled>() => cancelled_result(&e, book_info.book_name),
+                result => result?,
+            };
+            return Ok(UpdateResult {
+                new_chapters: new_count,
+                result,
+            });
+        }
+
+        let result: Result<DownloadResult> = async {
+            let mut fetched = self.load_checkpoints(book_id);
+            let pending: Vec<Chapter> = new_chapters
+                .iter()
+                .filter(|ch| !fetched.contains_key(&ch.id))
+                .cloned()
+                .collect();
+            let failed = self
+                .download_chapters_normal(&pending, &mut fetched, (10, 80), &ctx)
+                .await?;
+            let failed = self.retry_failed(failed, &mut fetched, &ctx).await?;
+
+            // 只追加连续获取成功的章节，第一个失败章节及之后的内容留到下次更新
+            let mut appended = Vec::new();
+            let mut contents = Vec::new();
+            for ch in new_chapters {
+                let Some(content) = fetched.remove(&ch.id) else {
+                    break;
+                };
+                contents.push(ChapterContent {
+                    title: ch.title.clone(),
+                    content: content.content,
+                    index: ch.index,
+                });
+                appended.push(ch);
+            }
+
+            self.proceed().await?;
+            ctx.emit_progress(85, 100, "正在写入文件...");
+            self.append_txt(&contents, &options.save_path)?;
+
+            let mut chapters = downloaded;
+            chapters.extend(appended);
+            self.record_library(&book_info, &options, &chapters, &[], total_chapters);
+            self.clear_checkpoints(book_id);
+
+            ctx.emit_progress(100, 100, &format!("更新完成，新增 {} 章", contents.len()));
+
+            Ok(DownloadResult {
+                success: true,
+                status: DownloadStatus::Completed,
+                file_path: Some(options.save_path.clone()),
+                error: None,
+                book_name: book_info.book_name.clone(),
+                failed_chapters: failed,
+            })
+        }
+        .await;
+
+        let result = match result {
+            Err(e) if e.is::<Cancelled>() => cancelled_result(&e, book_info.book_name),
+            result => result?,
+        };
+        Ok(UpdateResult {
+            new_chapters: new_count,
+            result,
         })
     }
 
+    /// 对失败章节进行重试，每轮等待时间加倍，返回最终仍失败的章节
+    async fn retry_failed(
+        &self,
+        mut failed: Vec<FailedChapter>,
+        fetched: &mut HashMap<String, ChapterContent>,
+        ctx: &TaskContext<'_>,
+    ) -> Result<Vec<FailedChapter>> {
+        for round in 1..=ctx.retry_rounds {
+            if failed.is_empty() {
+                break;
+            }
+            self.proceed().await?;
+
+            let delay = RETRY_BASE_DELAY * 2u32.pow(round - 1);
+            ctx.emit_progress(
+                80,
+                100,
+                &format!(
+                    "{} 章下载失败，{} 秒后进行第 {} 次重试...",
+                    failed.len(),
+                    delay.as_secs(),
+                    round
+                ),
+            );
+            self.sleep(delay).await?;
+
+            let retry: Vec<Chapter> = failed
+                .iter()
+                .map(|f| Chapter {
+                    id: f.id.clone(),
+                    title: f.title.clone(),
+                    index: f.index,
+                })
+                .collect();
+            failed = self
+                .download_chapters_normal(&retry, fetched, (80, 85), ctx)
+                .await?;
+        }
+        Ok(failed)
+    }
+
     /// 普通模式下载章节
+    ///
+    /// 最多同时发起 `ctx.concurrency` 个请求，每章获取后立即写入断点并放入 `fetched`，
+    /// 进度在 `progress` 给出的百分比区间内推进，返回本轮失败的章节。
     async fn download_chapters_normal(
         &self,
         chapters: &[Chapter],
-        app_handle: &AppHandle,
-        book_id: &str,
-    ) -> Result<Vec<ChapterContent>> {
+        fetched: &mut HashMap<String, ChapterContent>,
+        progress: (usize, usize),
+        ctx: &TaskContext<'_>,
+    ) -> Result<Vec<FailedChapter>> {
         let total = chapters.len();
-        let mut contents = Vec::with_capacity(total);
+        let mut failed = Vec::new();
 
-        for (idx, ch) in chapters.iter().enumerate() {
-            let progress = 25 + (idx as f64 / total as f64 * 60.0) as usize;
-            let _ = app_handle.emit(
-                "download-progress",
-                DownloadProgress {
-                    current: idx + 1,
-                    total,
-                    percent: progress as f64,
-                    message: format!("下载中: {}/{} - {}", idx + 1, total, ch.title),
-                    book_id: book_id.to_string(),
-                },
-            );
-
-            match self.api.get_chapter_content(&ch.id).await {
+        let mut tasks = stream::iter(chapters.iter().cloned())
+            .map(|ch| async move {
+                if let Err(e) = self.proceed().await {
+                    return (ch, Err(e));
+                }
+                let result = self.source.get_chapter_content(&ch.id).await;
+                (ch, result)
+            })
+            .buffer_unordered(ctx.concurrency);
+
+        // 按完成顺序计数，保证进度单调递增
+        let (start, end) = progress;
+        let mut completed = 0;
+        while let Some((ch, result)) = tasks.next().await {
+            // 取消后立即返回，丢弃仍在进行中的请求
+            if matches!(&result, Err(e) if e.is::<Cancelled>()) {
+                return Err(Cancelled.into());
+            }
+            completed += 1;
+            let percent = start + (completed as f64 / total as f64 * (end - start) as f64) as usize;
+            ctx.progress.report(DownloadProgress {
+                current: completed,
+                total,
+                percent: percent as f64,
+                message: format!("下载中: {}/{} - {}", completed, total, ch.title),
+                book_id: ctx.book_id.to_string(),
+            });
+
+            match result {
                 Ok(content) => {
-                    contents.push(ChapterContent {
+                    let content = ChapterContent {
                         title: ch.title.clone(),
                         content,
                         index: ch.index,
-                    });
+                    };
+                    self.save_checkpoint(ctx.book_id, &ch.id, &content);
+                    fetched.insert(ch.id.clone(), content);
                 }
                 Err(e) => {
-                    eprintln!("章节 {} 下载失败: {}", ch.title, e);
-                    // 继续下载其他章节
+                    // 记录失败，继续下载其他章节
+                    failed.push(FailedChapter {
+                        id: ch.id,
+                        title: ch.title,
+                        index: ch.index,
+                        error: e.to_string(),
+                    });
                 }
             }
+        }
+
+        failed.sort_by_key(|f| f.index);
+        Ok(failed)
+    }
+
+    /// 不联网，用书库记录和本地保存的章节重新生成文件
+    ///
+    /// 章节正文依次从断点和章节存储中读取，不修改书库记录。
+    pub fn export(
+        &self,
+        book_id: &str,
+        format: &str,
+        save_path: &str,
+        options: &ExportOptions,
+    ) -> Result<DownloadResult> {
+        let library = self
+            .library
+            .as_ref()
+            .ok_or_else(|| anyhow!("未启用书库，无法离线导出"))?;
+        let entry = library
+            .get(book_id)?
+            .ok_or_else(|| AppError::not_found("书库中没有该书籍，请先下载"))?;
+        let start = options.start_chapter.unwrap_or(0);
+        let end = options.end_chapter.unwrap_or(usize::MAX);
+        let chapters: Vec<Chapter> = library
+            .chapters(book_id)?
+            .into_iter()
+            .filter(|ch| ch.index >= start && ch.index < end)
+            .collect();
+        if chapters.is_empty() {
+            return Err(AppError::NoChapters.into());
+        }
+
+        let mut fetched = self.load_checkpoints(book_id);
+        let mut failed = Vec::new();
+        let chapter_contents: Vec<ChapterContent> = chapters
+            .iter()
+            .map(|ch| {
+                let content = fetched
+                    .remove(&ch.id)
+                    .map(|c| c.content)
+                    .or_else(|| self.chapters.as_ref().and_then(|store| store.get(&ch.id)))
+                    .unwrap_or_else(|| {
+                        failed.push(FailedChapter {
+                            id: ch.id.clone(),
+                            title: ch.title.clone(),
+                            index: ch.index,
+                            error: "本地没有该章节的内容".to_string(),
+                        });
+                        "（本章内容缺失，请重新下载）".to_string()
+                    });
+                ChapterContent {
+                    title: ch.title.clone(),
+                    content,
+                    index: ch.index,
+                }
+            })
+            .collect();
+
+        let policy = options.missing_chapters.unwrap_or(MissingChapterPolicy::Fail);
+        if !failed.is_empty() && policy == MissingChapterPolicy::Fail {
+            return Ok(DownloadResult {
+                success: false,
+                status: DownloadStatus::Failed,
+                file_path: None,
+                error: Some(format!("{} 个章节没有本地内容，需要重新下载", failed.len())),
+                book_name: entry.book_info.book_name,
+                failed_chapters: failed,
+            });
+        }
+
+        let file_path = self.write_book(
+            &format.to_lowercase(),
+            &entry.book_info,
+            self.local_cover(book_id),
+            &chapter_contents,
+            save_path,
+        )?;
+        Ok(DownloadResult {
+            success: true,
+            status: DownloadStatus::Completed,
+            file_path: Some(file_path),
+            error: None,
+            book_name: entry.book_info.book_name,
+            failed_chapters: failed,
+        })
+    }
+
+    /// 读取断点，失败时只记录日志并从头下载
+    fn load_checkpoints(&self, book_id: &str) -> HashMap<String, ChapterContent> {
+        match &self.checkpoints {
+            Some(store) => store.load(book_id).unwrap_or_else(|e| {
+                eprintln!("读取断点失败: {}", e);
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        }
+    }
+
+    /// 写入断点，失败时只记录日志不中断下载
+    fn save_checkpoint(&self, book_id: &str, chapter_id: &str, content: &ChapterContent) {
+        if let Some(store) = &self.checkpoints {
+            if let Err(e) = store.save(book_id, chapter_id, content) {
+                eprintln!("章节 {} 断点保存失败: {}", content.title, e);
+            }
+        }
+    }
 
-            // 添加小延迟避免请求过快
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    /// 文件生成后删除断点，章节正文已保存在章节存储中，不再重复占用空间
+    fn clear_checkpoints(&self, book_id: &str) {
+        if let Some(store) = &self.checkpoints {
+            if let Err(e) = store.clear(book_id) {
+                eprintln!("删除断点失败: {}", e);
+            }
         }
+    }
 
-        Ok(contents)
+    /// 记录到书库，失败时只记录日志
+    fn record_library(
+        &self,
+        book_info: &BookInfo,
+        options: &DownloadOptions,
+        chapters: &[Chapter],
+        missing: &[FailedChapter],
+        total_chapters: usize,
+    ) {
+        if let Some(library) = &self.library {
+            if let Err(e) = library.record(book_info, options, chapters, missing, total_chapters) {
+                eprintln!("书库记录失败: {}", e);
+            }
+        }
+    }
+
+    /// 按格式生成文件，EPUB 没有封面图片时使用生成的标题卡片
+    fn write_book(
+        &self,
+        format: &str,
+        book_info: &BookInfo,
+        cover: Option<Cover>,
+        chapters: &[ChapterContent],
+        save_path: &str,
+    ) -> Result<String> {
+        match format {
+            "epub" => {
+                let cover = cover.unwrap_or_else(|| Cover::title_card(book_info));
+                self.create_epub(book_info, &cover, chapters, save_path)
+            }
+            "txt" => self.create_txt(book_info, chapters, save_path),
+            _ => Err(unsupported_format(format).into()),
+        }
+    }
+
+    /// 下载封面并保存到本地；下载或转换失败时使用之前保存的封面，都没有时返回 `None`
+    async fn download_cover(&self, book_info: &BookInfo) -> Option<Cover> {
+        if book_info.cover_url.is_empty() {
+            return self.local_cover(&book_info.book_id);
+        }
+        match self
+            .source
+            .get_cover(&book_info.cover_url)
+            .await
+            .and_then(Cover::from_image)
+        {
+            Ok(cover) => {
+                if let Some(store) = &self.chapters {
+                    if let Err(e) = store.put_cover(&book_info.book_id, &cover.data) {
+                        eprintln!("封面保存失败: {}", e);
+                    }
+                }
+                Some(cover)
+            }
+            Err(e) => {
+                eprintln!("封面获取失败: {}", e);
+                self.local_cover(&book_info.book_id)
+            }
+        }
+    }
+
+    /// 本地保存的封面，不联网
+    fn local_cover(&self, book_id: &str) -> Option<Cover> {
+        let data = self.chapters.as_ref()?.cover(book_id)?;
+        Cover::from_image(data).ok()
     }
 
     /// 创建 TXT 文件
@@ -183,7 +790,7 @@ impl Downloader {
     ) -> Result<String> {
         let file_path = Path::new(save_path);
 
-        let file = File::create(&file_path)?;
+        let file = File::create(file_path)?;
         let mut writer = BufWriter::new(file);
 
         // 写入书籍信息
@@ -195,24 +802,31 @@ impl Downloader {
         writeln!(writer, "\n{}\n", "=".repeat(50))?;
 
         // 写入章节
-        for ch in chapters {
-            writeln!(writer, "\n{}\n", ch.title)?;
-            writeln!(writer, "{}\n", ch.content)?;
-        }
+        write_txt_chapters(&mut writer, chapters)?;
 
         Ok(file_path.to_string_lossy().to_string())
     }
 
+    /// 在已有 TXT 文件末尾追加章节
+    fn append_txt(&self, chapters: &[ChapterContent], save_path: &str) -> Result<()> {
+        let file = OpenOptions::new().append(true).open(save_path)?;
+        let mut writer = BufWriter::new(file);
+        write_txt_chapters(&mut writer, chapters)?;
+        writer.flush()?;
+        Ok(())
+    }
+
     /// 创建 EPUB 文件
     fn create_epub(
         &self,
         book_info: &BookInfo,
+        cover: &Cover,
         chapters: &[ChapterContent],
         save_path: &str,
     ) -> Result<String> {
         let file_path = Path::new(save_path);
 
-        let file = File::create(&file_path)?;
+        let file = File::create(file_path)?;
         let zip = ZipLibrary::new().map_err(|e| anyhow!("创建 ZIP 库失败: {}", e))?;
         let mut epub = EpubBuilder::new(zip).map_err(|e| anyhow!("创建 EPUB 构建器失败: {}", e))?;
 
@@ -229,6 +843,26 @@ impl Downloader {
                 .map_err(|e| anyhow!("设置描述失败: {}", e))?;
         }
 
+        // 封面图片和封面页
+        epub.add_cover_image(cover.file_name, cover.data.as_slice(), cover.mime_type)
+            .map_err(|e| anyhow!("添加封面失败: {}", e))?;
+        let cover_html = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>封面</title></head>
+<body style="margin:0;text-align:center">
+<img src="{}" alt="封面" style="max-width:100%;max-height:100%"/>
+</body>
+</html>"#,
+            cover.file_name
+        );
+        epub.add_content(
+            EpubContent::new("cover.xhtml", cover_html.as_bytes())
+                .title("封面")
+                .reftype(ReferenceType::Cover)
+        ).map_err(|e| anyhow!("添加封面页失败: {}", e))?;
+
         // 创建简介页
         let intro_html = format!(
             r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -243,9 +877,9 @@ impl Downloader {
 <p>{}</p>
 </body>
 </html>"#,
-            book_info.book_name,
-            book_info.author,
-            book_info.description.replace('\n', "<br/>")
+            escape_xml(&book_info.book_name),
+            escape_xml(&book_info.author),
+            escape_xml(&book_info.description).replace('\n', "<br/>")
         );
 
         epub.add_content(
@@ -253,11 +887,12 @@ impl Downloader {
                 .title("书籍信息")
         ).map_err(|e| anyhow!("添加简介页失败: {}", e))?;
 
-        // 添加章节
+        // 添加章节，正文和标题都要转义，否则 & 或 < 会让 XHTML 无效
         for (idx, ch) in chapters.iter().enumerate() {
+            let title = escape_xml(&ch.title);
             let content_html = ch.content
                 .split("\n\n")
-                .map(|p| format!("<p>{}</p>", p.trim()))
+                .map(|p| format!("<p>{}</p>", escape_xml(p.trim())))
                 .collect::<Vec<_>>()
                 .join("\n");
 
@@ -271,7 +906,7 @@ impl Downloader {
 <div>{}</div>
 </body>
 </html>"#,
-                ch.title, ch.title, content_html
+                title, title, content_html
             );
 
             epub.add_content(
@@ -292,3 +927,28 @@ impl Default for Downloader {
     }
 }
 
+/// 写入 TXT 章节正文
+fn write_txt_chapters(writer: &mut impl Write, chapters: &[ChapterContent]) -> Result<()> {
+    for ch in chapters {
+        writeln!(writer, "\n{}\n", ch.title)?;
+        writeln!(writer, "{}\n", ch.content)?;
+    }
+    Ok(())
+}
+
+/// 被取消的下载结果
+fn cancelled_result(error: &anyhow::Error, book_name: String) -> DownloadResult {
+    DownloadResult {
+        success: false,
+        status: DownloadStatus::Cancelled,
+        file_path: None,
+        error: Some(error.to_string()),
+        book_name,
+        failed_chapters: Vec::new(),
+    }
+}
+
+/// 不支持的文件格式
+fn unsupported_format(format: &str) -> AppError {
+    AppError::invalid_input(format!("不支持的格式: {}", format))
+}
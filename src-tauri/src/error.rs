@@ -0,0 +1,142 @@
+use crate::control::Cancelled;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// 收到响应但状态码异常或响应体无法解析
+#[derive(Debug, thiserror::Error)]
+#[error("HTTP {status}: {message}")]
+pub struct HttpError {
+    pub status: u16,
+    pub message: String,
+}
+
+/// 单个节点的一次尝试
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeAttempt {
+    pub base_url: String,
+    /// 收到响应时的 HTTP 状态码
+    pub status: Option<u16>,
+    pub elapsed_ms: u64,
+    pub error: String,
+    /// 节点处于熔断中，未实际发送请求
+    pub skipped: bool,
+}
+
+/// 所有节点都失败，按尝试顺序记录每个节点的错误
+#[derive(Debug, Clone, Default, Serialize, thiserror::Error)]
+pub struct FallbackError {
+    pub attempts: Vec<NodeAttempt>,
+}
+
+impl std::fmt::Display for FallbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.attempts.is_empty() {
+            return write!(f, "没有启用的 API 节点");
+        }
+        write!(f, "所有 API 节点均不可用")?;
+        for (i, attempt) in self.attempts.iter().enumerate() {
+            let sep = if i == 0 { "：" } else { "；" };
+            write!(f, "{}{} {}", sep, attempt.base_url, attempt.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// 应用错误
+///
+/// 内部仍以 `anyhow::Result` 传递，在命令边界通过 `From<anyhow::Error>` 还原为具体类型，
+/// 序列化为 `{ code, message, attempts }` 交给前端按 `code` 分别处理。
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("书籍已下架")]
+    BookRemoved,
+    #[error("没有可下载的章节")]
+    NoChapters,
+    #[error(transparent)]
+    AllSourcesDown(#[from] FallbackError),
+    #[error("数据解析失败: {0}")]
+    ParseError(String),
+    /// 接口返回的数据与模型不符，通常是节点升级导致
+    #[error("接口数据结构已变化: {0}")]
+    SchemaChanged(String),
+    #[error("文件读写失败: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("下载已取消")]
+    Cancelled,
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    AlreadyExists(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AppError {
+    /// 供前端判断的错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BookRemoved => "book_removed",
+            Self::NoChapters => "no_chapters",
+            Self::AllSourcesDown(_) => "all_sources_down",
+            Self::ParseError(_) => "parse_error",
+            Self::SchemaChanged(_) => "schema_changed",
+            Self::IoError(_) => "io_error",
+            Self::Cancelled => "cancelled",
+            Self::NotFound(_) => "not_found",
+            Self::AlreadyExists(_) => "already_exists",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::Other(_) => "other",
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn already_exists(message: impl Into<String>) -> Self {
+        Self::AlreadyExists(message.into())
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::InvalidInput(message.into())
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<AppError>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<FallbackError>() {
+            Ok(e) => return Self::AllSourcesDown(e),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<std::io::Error>() {
+            Ok(e) => return Self::IoError(e),
+            Err(e) => e,
+        };
+        if e.is::<Cancelled>() {
+            return Self::Cancelled;
+        }
+        if e.is::<serde_json::Error>() {
+            return Self::ParseError(e.to_string());
+        }
+        Self::Other(e.to_string())
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        match self {
+            Self::AllSourcesDown(e) => state.serialize_field("attempts", &e.attempts)?,
+            _ => state.skip_field("attempts")?,
+        }
+        state.end()
+    }
+}
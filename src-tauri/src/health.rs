@@ -0,0 +1,158 @@
+#[cfg(feature = "gui")]
+use crate::api::FanqieApi;
+use crate::library::unix_now;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(feature = "gui")]
+use tauri::AppHandle;
+
+/// 计算错误率时保留的最近请求数
+const WINDOW: usize = 20;
+/// 新延迟样本的权重
+const LATENCY_WEIGHT: f64 = 0.3;
+/// 错误率达到该值的节点排在未测过的节点之后
+const UNHEALTHY_ERROR_RATE: f64 = 0.5;
+/// 后台探测间隔
+#[cfg(feature = "gui")]
+const PROBE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// 单个节点的统计
+#[derive(Debug, Default)]
+struct NodeStats {
+    /// 平滑后的延迟（毫秒）
+    latency_ms: Option<f64>,
+    /// 最近的请求结果，true 表示成功
+    recent: VecDeque<bool>,
+    last_error: Option<String>,
+    checked_at: Option<u64>,
+}
+
+impl NodeStats {
+    fn push(&mut self, ok: bool) {
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(ok);
+        self.checked_at = Some(unix_now());
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let errors = self.recent.iter().filter(|ok| !**ok).count();
+        errors as f64 / self.recent.len() as f64
+    }
+
+    /// 分数越低越优先，延迟按错误率放大
+    fn score(&self) -> Option<f64> {
+        if self.recent.is_empty() {
+            return None;
+        }
+        let latency = self.latency_ms.unwrap_or(f64::from(u32::MAX));
+        Some(latency * (1.0 + 4.0 * self.error_rate()))
+    }
+
+    fn tier(&self) -> u8 {
+        match self.score() {
+            None => 1,
+            Some(_) if self.error_rate() >= UNHEALTHY_ERROR_RATE => 2,
+            Some(_) => 0,
+        }
+    }
+}
+
+/// 节点健康状况，供前端展示
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceHealth {
+    pub base_url: String,
+    pub latency_ms: Option<u64>,
+    pub error_rate: f64,
+    /// 参与统计的请求数
+    pub samples: usize,
+    pub score: Option<f64>,
+    pub last_error: Option<String>,
+    /// 最近一次请求或探测的时间（Unix 秒）
+    pub checked_at: Option<u64>,
+    /// 是否因连续失败被暂时跳过
+    pub circuit_open: bool,
+}
+
+/// 节点健康统计
+///
+/// 记录每个节点的延迟和最近的错误率，用于决定回退顺序。
+#[derive(Debug, Clone, Default)]
+pub struct HealthTracker {
+    nodes: Arc<Mutex<HashMap<String, NodeStats>>>,
+}
+
+impl HealthTracker {
+    pub fn record_success(&self, base_url: &str, elapsed: Duration) {
+        let mut nodes = self.nodes.lock().unwrap();
+        let stats = nodes.entry(base_url.to_string()).or_default();
+        let sample = elapsed.as_secs_f64() * 1000.0;
+        stats.latency_ms = Some(match stats.latency_ms {
+            Some(latency) => latency * (1.0 - LATENCY_WEIGHT) + sample * LATENCY_WEIGHT,
+            None => sample,
+        });
+        stats.push(true);
+    }
+
+    pub fn record_failure(&self, base_url: &str, error: &str) {
+        let mut nodes = self.nodes.lock().unwrap();
+        let stats = nodes.entry(base_url.to_string()).or_default();
+        stats.last_error = Some(error.to_string());
+        stats.push(false);
+    }
+
+    /// 按分数排列节点：健康节点按分数升序，其后是未测过的节点，最后是错误率过高的节点；
+    /// 同档内保持原有顺序
+    pub fn rank(&self, base_urls: &[String]) -> Vec<String> {
+        let nodes = self.nodes.lock().unwrap();
+        let mut ranked: Vec<(u8, f64, &String)> = base_urls
+            .iter()
+            .map(|url| match nodes.get(url) {
+                Some(stats) => (stats.tier(), stats.score().unwrap_or(0.0), url),
+                None => (1, 0.0, url),
+            })
+            .collect();
+        ranked.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        ranked.into_iter().map(|(_, _, url)| url.clone()).collect()
+    }
+
+    /// 给定节点的健康状况
+    pub fn snapshot(&self, base_urls: &[String]) -> Vec<SourceHealth> {
+        let nodes = self.nodes.lock().unwrap();
+        base_urls
+            .iter()
+            .map(|url| {
+                let stats = nodes.get(url);
+                SourceHealth {
+                    base_url: url.clone(),
+                    latency_ms: stats.and_then(|s| s.latency_ms).map(|ms| ms.round() as u64),
+                    error_rate: stats.map_or(0.0, NodeStats::error_rate),
+                    samples: stats.map_or(0, |s| s.recent.len()),
+                    score: stats.and_then(NodeStats::score),
+                    last_error: stats.and_then(|s| s.last_error.clone()),
+                    checked_at: stats.and_then(|s| s.checked_at),
+                    circuit_open: false,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 启动后台探测，启动时立即探测一次
+#[cfg(feature = "gui")]
+pub fn spawn_prober(app_handle: AppHandle) {
+    let api = FanqieApi::for_app(&app_handle);
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PROBE_INTERVAL);
+        loop {
+            interval.tick().await;
+            api.probe_sources().await;
+        }
+    });
+}
@@ -1,24 +1,30 @@
 // 模块定义
-mod api;
+pub mod api;
+#[cfg(feature = "gui")]
+mod app;
+pub mod book_source;
+pub mod breaker;
+pub mod cache;
+pub mod chapter_store;
+pub mod checkpoint;
+#[cfg(feature = "gui")]
 mod commands;
-mod downloader;
-mod types;
+pub mod control;
+pub mod cover;
+pub mod downloader;
+pub mod error;
+pub mod health;
+pub mod library;
+pub mod limiter;
+pub mod network;
+pub mod paths;
+pub mod progress;
+#[cfg(feature = "gui")]
+mod queue;
+pub mod sources;
+pub mod types;
+#[cfg(feature = "gui")]
+mod updater;
 
-use commands::{download_book, get_api_sources, get_book_detail, get_chapters, search_books};
-
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![
-            search_books,
-            get_book_detail,
-            get_chapters,
-            download_book,
-            get_api_sources,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
+#[cfg(feature = "gui")]
+pub use app::run;
@@ -0,0 +1,391 @@
+use crate::types::{BookInfo, Chapter, DownloadOptions, FailedChapter};
+use anyhow::Result;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 书库中的一本书
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub book_info: BookInfo,
+    /// 生成文件时使用的下载选项
+    pub options: DownloadOptions,
+    pub format: String,
+    pub file_path: String,
+    /// 首次下载时间（Unix 秒）
+    pub downloaded_at: u64,
+    /// 最后一次下载或增量更新的时间（Unix 秒），没有新章节时也会更新
+    pub updated_at: u64,
+    /// 最近一次获取目录时的总章节数
+    pub chapter_count: usize,
+    /// 文件中已包含的章节数
+    pub downloaded_chapters: usize,
+}
+
+/// 关注的书籍及上次检查时的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowedBook {
+    pub book_id: String,
+    pub book_name: String,
+    pub chapter_count: usize,
+    pub status: Option<String>,
+    /// 发现新章节时自动加入增量更新队列
+    pub auto_download: bool,
+    pub followed_at: u64,
+    pub checked_at: u64,
+}
+
+/// 本地书库，记录已下载的书籍及其章节清单
+#[derive(Clone)]
+pub struct Library {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Library {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS books (
+                book_id TEXT PRIMARY KEY,
+                book_name TEXT NOT NULL,
+                author TEXT NOT NULL,
+                book_info TEXT NOT NULL,
+                options TEXT NOT NULL,
+                format TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                downloaded_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                chapter_count INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS chapters (
+                book_id TEXT NOT NULL,
+                chapter_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                chapter_index INTEGER NOT NULL,
+                missing INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (book_id, chapter_id)
+            );
+            CREATE TABLE IF NOT EXISTS follows (
+                book_id TEXT PRIMARY KEY,
+                book_name TEXT NOT NULL,
+                chapter_count INTEGER NOT NULL,
+                status TEXT,
+                auto_download INTEGER NOT NULL,
+                followed_at INTEGER NOT NULL,
+                checked_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS follow_chapters (
+                book_id TEXT NOT NULL,
+                chapter_id TEXT NOT NULL,
+                PRIMARY KEY (book_id, chapter_id)
+            );",
+        )?;
+        // 旧版本的章节表没有 missing 列
+        let has_missing = conn
+            .prepare("SELECT 1 FROM pragma_table_info('chapters') WHERE name = 'missing'")?
+            .exists([])?;
+        if !has_missing {
+            conn.execute(
+                "ALTER TABLE chapters ADD COLUMN missing INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// 记录一次下载或更新，`chapters` 为文件中已包含的章节，其中 `missing` 的章节在文件里
+    /// 只有占位文本
+    pub fn record(
+        &self,
+        book_info: &BookInfo,
+        options: &DownloadOptions,
+        chapters: &[Chapter],
+        missing: &[FailedChapter],
+        chapter_count: usize,
+    ) -> Result<()> {
+        let now = unix_now() as i64;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO books (book_id, book_name, author, book_info, options, format, file_path,
+                                downloaded_at, updated_at, chapter_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9)
+             ON CONFLICT(book_id) DO UPDATE SET
+                book_name = excluded.book_name,
+                author = excluded.author,
+                book_info = excluded.book_info,
+                options = excluded.options,
+                format = excluded.format,
+                file_path = excluded.file_path,
+                updated_at = excluded.updated_at,
+                chapter_count = excluded.chapter_count",
+            params![
+                book_info.book_id,
+                book_info.book_name,
+                book_info.author,
+                serde_json::to_string(book_info)?,
+                serde_json::to_string(options)?,
+                options.format.to_lowercase(),
+                options.save_path,
+                now,
+                chapter_count as i64,
+            ],
+        )?;
+        tx.execute("DELETE FROM chapters WHERE book_id = ?1", params![book_info.book_id])?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO chapters (book_id, chapter_id, title, chapter_index, missing)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for ch in chapters {
+                let is_missing = missing.iter().any(|m| m.id == ch.id);
+                insert.execute(params![
+                    book_info.book_id,
+                    ch.id,
+                    ch.title,
+                    ch.index as i64,
+                    is_missing
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 列出书库，`keyword` 按书名或作者筛选
+    pub fn list(&self, keyword: Option<&str>) -> Result<Vec<LibraryEntry>> {
+        let pattern = format!("%{}%", keyword.unwrap_or("").trim());
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE b.book_name LIKE ?1 OR b.author LIKE ?1 ORDER BY b.updated_at DESC",
+            ENTRY_QUERY
+        ))?;
+        let entries = stmt
+            .query_map(params![pattern], read_entry)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    pub fn get(&self, book_id: &str) -> Result<Option<LibraryEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let entry = conn
+            .query_row(
+                &format!("{} WHERE b.book_id = ?1", ENTRY_QUERY),
+                params![book_id],
+                read_entry,
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    /// 文件中已包含的章节，按目录顺序排列
+    pub fn chapters(&self, book_id: &str) -> Result<Vec<Chapter>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT chapter_id, title, chapter_index FROM chapters
+             WHERE book_id = ?1 ORDER BY chapter_index",
+        )?;
+        let chapters = stmt
+            .query_map(params![book_id], |row| {
+                Ok(Chapter {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    index: row.get::<_, i64>(2)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(chapters)
+    }
+
+    /// 文件中只有占位文本的章节 id
+    pub fn missing_chapters(&self, book_id: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT chapter_id FROM chapters WHERE book_id = ?1 AND missing = 1")?;
+        let ids = stmt
+            .query_map(params![book_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ids)
+    }
+
+    pub fn remove(&self, book_id: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM chapters WHERE book_id = ?1", params![book_id])?;
+        tx.execute("DELETE FROM books WHERE book_id = ?1", params![book_id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 增量更新没有新章节时刷新书籍信息、总章节数和更新时间，文件和章节清单不变
+    pub fn refresh(&self, book_info: &BookInfo, chapter_count: usize) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE books SET book_name = ?2, author = ?3, book_info = ?4, updated_at = ?5,
+                              chapter_count = ?6
+             WHERE book_id = ?1",
+            params![
+                book_info.book_id,
+                book_info.book_name,
+                book_info.author,
+                serde_json::to_string(book_info)?,
+                unix_now() as i64,
+                chapter_count as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 更新最近一次获取目录时的总章节数
+    pub fn set_chapter_count(&self, book_id: &str, chapter_count: usize) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE books SET chapter_count = ?2 WHERE book_id = ?1",
+            params![book_id, chapter_count as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 关注书籍，已关注时更新快照和自动下载设置，`chapters` 为当前目录
+    pub fn follow(&self, book: &FollowedBook, chapters: &[Chapter]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO follows (book_id, book_name, chapter_count, status, auto_download,
+                                  followed_at, checked_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(book_id) DO UPDATE SET
+                book_name = excluded.book_name,
+                chapter_count = excluded.chapter_count,
+                status = excluded.status,
+                auto_download = excluded.auto_download,
+                checked_at = excluded.checked_at",
+            params![
+                book.book_id,
+                book.book_name,
+                book.chapter_count as i64,
+                book.status,
+                book.auto_download,
+                book.followed_at as i64,
+                book.checked_at as i64,
+            ],
+        )?;
+        replace_follow_chapters(&tx, &book.book_id, chapters)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn unfollow(&self, book_id: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM follow_chapters WHERE book_id = ?1", params![book_id])?;
+        tx.execute("DELETE FROM follows WHERE book_id = ?1", params![book_id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 上次检查时目录中的章节 id
+    pub fn follow_chapter_ids(&self, book_id: &str) -> Result<HashSet<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT chapter_id FROM follow_chapters WHERE book_id = ?1")?;
+        let ids = stmt
+            .query_map(params![book_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<HashSet<_>>>()?;
+        Ok(ids)
+    }
+
+    pub fn followed(&self) -> Result<Vec<FollowedBook>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT book_id, book_name, chapter_count, status, auto_download, followed_at, checked_at
+             FROM follows ORDER BY followed_at",
+        )?;
+        let books = stmt
+            .query_map([], |row| {
+                Ok(FollowedBook {
+                    book_id: row.get(0)?,
+                    book_name: row.get(1)?,
+                    chapter_count: row.get::<_, i64>(2)? as usize,
+                    status: row.get(3)?,
+                    auto_download: row.get(4)?,
+                    followed_at: row.get::<_, i64>(5)? as u64,
+                    checked_at: row.get::<_, i64>(6)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(books)
+    }
+
+    /// 记录一次更新检查的结果，`chapters` 为最新目录
+    pub fn update_follow(
+        &self,
+        book_id: &str,
+        book_name: &str,
+        chapters: &[Chapter],
+        status: Option<&str>,
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE follows SET book_name = ?2, chapter_count = ?3, status = ?4, checked_at = ?5
+             WHERE book_id = ?1",
+            params![book_id, book_name, chapters.len() as i64, status, unix_now() as i64],
+        )?;
+        replace_follow_chapters(&tx, book_id, chapters)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// 替换关注书籍的章节快照
+fn replace_follow_chapters(tx: &Transaction, book_id: &str, chapters: &[Chapter]) -> Result<()> {
+    tx.execute("DELETE FROM follow_chapters WHERE book_id = ?1", params![book_id])?;
+    let mut insert =
+        tx.prepare("INSERT OR IGNORE INTO follow_chapters (book_id, chapter_id) VALUES (?1, ?2)")?;
+    for ch in chapters {
+        insert.execute(params![book_id, ch.id])?;
+    }
+    Ok(())
+}
+
+/// 当前 Unix 时间（秒）
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+const ENTRY_QUERY: &str = "SELECT b.book_info, b.options, b.format, b.file_path, b.downloaded_at,
+        b.updated_at, b.chapter_count,
+        (SELECT COUNT(*) FROM chapters c WHERE c.book_id = b.book_id AND c.missing = 0)
+     FROM books b";
+
+fn read_entry(row: &Row) -> rusqlite::Result<LibraryEntry> {
+    Ok(LibraryEntry {
+        book_info: json_column(row, 0)?,
+        options: json_column(row, 1)?,
+        format: row.get(2)?,
+        file_path: row.get(3)?,
+        downloaded_at: row.get::<_, i64>(4)? as u64,
+        updated_at: row.get::<_, i64>(5)? as u64,
+        chapter_count: row.get::<_, i64>(6)? as usize,
+        downloaded_chapters: row.get::<_, i64>(7)? as usize,
+    })
+}
+
+/// 读取以 JSON 保存的列
+fn json_column<T: DeserializeOwned>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(idx)?;
+    serde_json::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
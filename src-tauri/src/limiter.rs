@@ -0,0 +1,84 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// 节点未单独配置时每秒允许的请求数
+pub const DEFAULT_RATE: f64 = 10.0;
+/// 令牌桶容量，允许短时间内的突发请求
+const BURST: f64 = 10.0;
+/// 需要排队时额外等待的随机时长上限，避免并发请求在同一时刻扎堆
+const MAX_JITTER: Duration = Duration::from_millis(200);
+/// 降速后不低于配置速率的这个比例
+const MIN_FACTOR: f64 = 1.0 / 16.0;
+
+#[derive(Debug)]
+struct Bucket {
+    /// 可用令牌数，为负时表示已有请求在排队
+    tokens: f64,
+    updated: Instant,
+    /// 实际速率相对配置速率的比例，节点出错时降低，成功后逐步恢复
+    factor: f64,
+}
+
+/// 按节点限速的令牌桶
+///
+/// 搜索、详情和所有下载任务的请求都经过同一个限速器，节点返回错误或 429/503
+/// 时自动降速。克隆后共享同一份状态。
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    nodes: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl RateLimiter {
+    /// 取得一个令牌，令牌不足时等待；`rate` 为该节点配置的每秒请求数
+    pub async fn acquire(&self, base_url: &str, rate: f64) {
+        let wait = self.reserve(base_url, rate);
+        if !wait.is_zero() {
+            let jitter = MAX_JITTER.mul_f64(rand::thread_rng().gen::<f64>());
+            tokio::time::sleep(wait + jitter).await;
+        }
+    }
+
+    /// 预留一个令牌，返回需要等待的时长
+    fn reserve(&self, base_url: &str, rate: f64) -> Duration {
+        let mut nodes = self.nodes.lock().unwrap();
+        let now = Instant::now();
+        let bucket = nodes.entry(base_url.to_string()).or_insert(Bucket {
+            tokens: BURST,
+            updated: now,
+            factor: 1.0,
+        });
+        let rate = rate * bucket.factor;
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(BURST);
+        bucket.updated = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    }
+
+    /// 请求成功，逐步恢复到配置速率
+    pub fn record_success(&self, base_url: &str) {
+        if let Some(bucket) = self.nodes.lock().unwrap().get_mut(base_url) {
+            bucket.factor = (bucket.factor * 1.25).min(1.0);
+        }
+    }
+
+    /// 请求失败时降速；429/503 说明节点已经过载，降速更多并清空积攒的令牌
+    pub fn record_failure(&self, base_url: &str, status: Option<u16>) {
+        if let Some(bucket) = self.nodes.lock().unwrap().get_mut(base_url) {
+            if matches!(status, Some(429 | 503)) {
+                bucket.factor *= 0.5;
+                bucket.tokens = bucket.tokens.min(0.0);
+            } else {
+                bucket.factor *= 0.8;
+            }
+            bucket.factor = bucket.factor.max(MIN_FACTOR);
+        }
+    }
+}
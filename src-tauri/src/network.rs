@@ -0,0 +1,169 @@
+use crate::error::AppError;
+use crate::paths::write_durable;
+use anyhow::Result;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Proxy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// 代理设为这个值时不使用任何代理，包括系统代理
+pub const DIRECT: &str = "direct";
+
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36";
+
+/// 网络设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// 全局代理，支持 http://、https://、socks5:// 和 socks5h://；未设置时使用系统代理
+    pub proxy: Option<String>,
+    pub connect_timeout_secs: u64,
+    /// 两次收到数据之间的最长等待时间，不限制整个请求的耗时，批量接口的大响应不会被截断
+    #[serde(alias = "timeout_secs")]
+    pub read_timeout_secs: u64,
+    pub user_agent: Option<String>,
+    /// 附加的请求头，与默认请求头同名时覆盖默认值
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            user_agent: None,
+            headers: BTreeMap::new(),
+        }
+    }
+}
+
+impl NetworkSettings {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(proxy) = &self.proxy {
+            validate_proxy(proxy)?;
+        }
+        if self.connect_timeout_secs == 0 || self.read_timeout_secs == 0 {
+            return Err(AppError::invalid_input("超时时间必须大于 0 秒").into());
+        }
+        self.header_map()?;
+        Ok(())
+    }
+
+    /// 按设置创建 HTTP 客户端，`proxy` 为实际使用的代理，[`DIRECT`] 表示直连
+    pub fn build_client(&self, proxy: Option<&str>) -> Result<Client> {
+        let mut builder = Client::builder()
+            .read_timeout(Duration::from_secs(self.read_timeout_secs))
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+            .default_headers(self.header_map()?);
+        match proxy {
+            None => {}
+            Some(DIRECT) => builder = builder.no_proxy(),
+            Some(proxy) => builder = builder.proxy(parse_proxy(proxy)?),
+        }
+        Ok(builder.build()?)
+    }
+
+    fn header_map(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Referer", HeaderValue::from_static("https://fanqienovel.com/"));
+        headers.insert("X-Requested-With", HeaderValue::from_static("XMLHttpRequest"));
+        headers.insert(
+            "Accept-Language",
+            HeaderValue::from_static("zh-CN,zh;q=0.9,en-US;q=0.8,en;q=0.7"),
+        );
+        headers.insert(
+            "Accept",
+            HeaderValue::from_static("application/json, text/javascript, */*; q=0.01"),
+        );
+        for (name, value) in &self.headers {
+            let name = HeaderName::try_from(name.trim())
+                .map_err(|_| AppError::invalid_input(format!("无效的请求头名称: {}", name)))?;
+            let value = HeaderValue::try_from(value.trim())
+                .map_err(|_| AppError::invalid_input(format!("无效的请求头内容: {}", value)))?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+/// 检查代理地址，[`DIRECT`] 表示直连
+pub fn validate_proxy(proxy: &str) -> Result<()> {
+    if proxy != DIRECT {
+        parse_proxy(proxy)?;
+    }
+    Ok(())
+}
+
+fn parse_proxy(proxy: &str) -> Result<Proxy> {
+    const SCHEMES: [&str; 4] = ["http://", "https://", "socks5://", "socks5h://"];
+    if !SCHEMES.iter().any(|scheme| proxy.starts_with(scheme)) {
+        return Err(AppError::invalid_input(
+            "代理地址必须以 http://、https://、socks5:// 或 socks5h:// 开头",
+        )
+        .into());
+    }
+    Proxy::all(proxy).map_err(|e| AppError::invalid_input(format!("无效的代理地址: {}", e)).into())
+}
+
+/// 连接测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionTest {
+    pub base_url: String,
+    /// 实际使用的代理，未设置时为系统代理
+    pub proxy: Option<String>,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// 网络设置存储
+///
+/// 与 [`SourceStore`](crate::sources::SourceStore) 相同，通过 [`NetworkStore::load`]
+/// 打开时每次修改都会写回磁盘。
+#[derive(Debug, Clone, Default)]
+pub struct NetworkStore {
+    path: Option<PathBuf>,
+    settings: Arc<RwLock<NetworkSettings>>,
+}
+
+impl NetworkStore {
+    /// 仅在内存中使用给定的设置，不写入磁盘
+    pub fn new(settings: NetworkSettings) -> Self {
+        Self {
+            path: None,
+            settings: Arc::new(RwLock::new(settings)),
+        }
+    }
+
+    /// 从磁盘读取网络设置，文件不存在或损坏时使用默认设置
+    pub fn load(path: PathBuf) -> Self {
+        let settings = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            settings: Arc::new(RwLock::new(settings)),
+        }
+    }
+
+    pub fn get(&self) -> NetworkSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    pub fn set(&self, settings: NetworkSettings) -> Result<()> {
+        settings.validate()?;
+        if let Some(path) = &self.path {
+            write_durable(path, &serde_json::to_vec_pretty(&settings)?)?;
+        }
+        *self.settings.write().unwrap() = settings;
+        Ok(())
+    }
+}
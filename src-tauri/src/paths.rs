@@ -0,0 +1,74 @@
+#[cfg(feature = "cli")]
+use anyhow::anyhow;
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager};
+
+/// 与 tauri.conf.json 中的 identifier 保持一致
+pub const APP_IDENTIFIER: &str = "com.tomato.novel.manager";
+
+/// 不依赖 Tauri 的应用数据目录，与 GUI 使用的 `app_data_dir` 相同，供命令行工具共用配置和书库
+#[cfg(feature = "cli")]
+pub fn data_dir() -> Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .ok_or_else(|| anyhow!("无法确定应用数据目录"))
+}
+
+/// 应用数据目录下的文件或子目录
+#[cfg(feature = "gui")]
+pub fn app_data_path(app_handle: &AppHandle, name: &str) -> Result<PathBuf> {
+    Ok(app_handle.path().app_data_dir()?.join(name))
+}
+
+/// 写入文件：先写临时文件再重命名，崩溃时不会留下半个文件覆盖原有内容
+///
+/// 不等待数据落盘，用于章节、断点和缓存这类丢失后可以重新获取的数据。
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    write_via_temp(path, contents, false)
+}
+
+/// 与 [`write_atomic`] 相同，重命名前等待数据落盘，用于配置和下载队列
+pub fn write_durable(path: &Path, contents: &[u8]) -> Result<()> {
+    write_via_temp(path, contents, true)
+}
+
+fn write_via_temp(path: &Path, contents: &[u8], sync: bool) -> Result<()> {
+    /// 同一进程内区分临时文件，同时写入同一路径时互不覆盖
+    static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        if sync {
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    Ok(result?)
+}
+
+/// 只保留可安全用作文件名的字符
+pub fn sanitize_file_name(id: &str) -> String {
+    id.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
@@ -0,0 +1,45 @@
+use crate::types::DownloadProgress;
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+/// 下载进度的接收方
+///
+/// 下载流程只通过它汇报进度，不依赖具体的界面或运行环境。
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, progress: DownloadProgress);
+}
+
+/// 以 `download-progress` 事件发送给前端
+#[cfg(feature = "gui")]
+impl ProgressSink for AppHandle {
+    fn report(&self, progress: DownloadProgress) {
+        let _ = self.emit("download-progress", progress);
+    }
+}
+
+/// 把进度发送到通道，供命令行或测试读取
+pub struct ChannelProgress {
+    sender: mpsc::UnboundedSender<DownloadProgress>,
+}
+
+impl ChannelProgress {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<DownloadProgress>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl ProgressSink for ChannelProgress {
+    fn report(&self, progress: DownloadProgress) {
+        // 接收端已关闭时直接丢弃
+        let _ = self.sender.send(progress);
+    }
+}
+
+/// 忽略所有进度
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _progress: DownloadProgress) {}
+}
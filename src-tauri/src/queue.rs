@@ -0,0 +1,318 @@
+use crate::control::DownloadRegistry;
+use crate::downloader::Downloader;
+use crate::error::AppError;
+use crate::paths::write_durable;
+use crate::types::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 默认同时进行的下载数
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+
+/// 队列任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// 队列任务类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueJobKind {
+    /// 完整下载
+    #[default]
+    Download,
+    /// 增量更新书库中已有的书籍
+    Update,
+}
+
+/// 队列中的下载任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueItem {
+    /// 任务编号，同一本书移出后重新加入会得到新的编号
+    #[serde(default)]
+    pub job_id: u64,
+    pub book_id: String,
+    #[serde(default)]
+    pub kind: QueueJobKind,
+    pub options: DownloadOptions,
+    pub status: QueueStatus,
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub failed_chapters: Vec<FailedChapter>,
+    /// 加入队列的时间（Unix 秒）
+    pub added_at: u64,
+}
+
+/// 持久化的队列内容
+#[derive(Debug, Serialize, Deserialize)]
+struct QueueState {
+    max_concurrent: usize,
+    items: Vec<QueueItem>,
+    #[serde(default)]
+    next_job_id: u64,
+}
+
+impl Default for QueueState {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            items: Vec::new(),
+            next_job_id: 1,
+        }
+    }
+}
+
+impl QueueState {
+    fn new_job_id(&mut self) -> u64 {
+        let job_id = self.next_job_id.max(1);
+        self.next_job_id = job_id + 1;
+        job_id
+    }
+}
+
+struct QueueInner {
+    app_handle: AppHandle,
+    path: PathBuf,
+    state: Mutex<QueueState>,
+}
+
+/// 下载队列
+///
+/// 按顺序最多同时运行 `max_concurrent` 个下载任务，每次变更都会写回磁盘
+/// 并发送 `queue-changed` 事件。
+#[derive(Clone)]
+pub struct DownloadQueue {
+    inner: Arc<QueueInner>,
+}
+
+impl DownloadQueue {
+    /// 从磁盘恢复队列，上次退出时仍在进行的任务会重新排队
+    pub fn load(app_handle: AppHandle, path: PathBuf) -> Self {
+        let mut state: QueueState = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        // 旧版本保存的队列没有任务编号
+        state.next_job_id = state.items.iter().map(|item| item.job_id).max().unwrap_or(0) + 1;
+        for item in &mut state.items {
+            if item.job_id == 0 {
+                item.job_id = state.next_job_id;
+                state.next_job_id += 1;
+            }
+            if item.status == QueueStatus::Running {
+                item.status = QueueStatus::Pending;
+            }
+        }
+
+        Self {
+            inner: Arc::new(QueueInner {
+                app_handle,
+                path,
+                state: Mutex::new(state),
+            }),
+        }
+    }
+
+    pub fn list(&self) -> Vec<QueueItem> {
+        self.inner.state.lock().unwrap().items.clone()
+    }
+
+    /// 添加下载任务
+    pub fn enqueue(&self, options: DownloadOptions) -> Result<()> {
+        self.push(QueueJobKind::Download, options)
+    }
+
+    /// 添加增量更新任务，`options` 为书库中记录的下载选项
+    pub fn enqueue_update(&self, options: DownloadOptions) -> Result<()> {
+        self.push(QueueJobKind::Update, options)
+    }
+
+    fn push(&self, kind: QueueJobKind, options: DownloadOptions) -> Result<()> {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            let active = state.items.iter().any(|item| {
+                item.book_id == options.book_id
+                    && matches!(item.status, QueueStatus::Pending | QueueStatus::Running)
+            });
+            if active {
+                return Err(AppError::already_exists("该书籍已在下载队列中").into());
+            }
+
+            // 已结束的同名任务直接替换
+            state.items.retain(|item| item.book_id != options.book_id);
+            let job_id = state.new_job_id();
+            state.items.push(QueueItem {
+                job_id,
+                book_id: options.book_id.clone(),
+                kind,
+                options,
+                status: QueueStatus::Pending,
+                file_path: None,
+                error: None,
+                failed_chapters: Vec::new(),
+                added_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+            });
+            self.commit(&state);
+        }
+        self.start();
+        Ok(())
+    }
+
+    /// 按给定的 book_id 顺序重排队列，未列出的任务保持原有顺序排在最后
+    pub fn reorder(&self, book_ids: &[String]) {
+        let mut state = self.inner.state.lock().unwrap();
+        let mut items = std::mem::take(&mut state.items);
+        let mut reordered = Vec::with_capacity(items.len());
+        for book_id in book_ids {
+            if let Some(pos) = items.iter().position(|item| &item.book_id == book_id) {
+                reordered.push(items.remove(pos));
+            }
+        }
+        reordered.extend(items);
+        state.items = reordered;
+        self.commit(&state);
+    }
+
+    /// 从队列移除任务，正在进行的下载会被取消
+    pub fn remove(&self, book_id: &str) -> Result<()> {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            let pos = state
+                .items
+                .iter()
+                .position(|item| item.book_id == book_id)
+                .ok_or_else(|| AppError::not_found("队列中没有该书籍"))?;
+            let item = state.items.remove(pos);
+            if item.status == QueueStatus::Running {
+                // 立即注销，同一本书可以马上重新加入队列
+                let registry = self.inner.app_handle.state::<DownloadRegistry>();
+                if let Ok(control) = registry.get(book_id) {
+                    control.cancel();
+                    registry.release(book_id, &control);
+                }
+            }
+            self.commit(&state);
+        }
+        self.start();
+        Ok(())
+    }
+
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            state.max_concurrent = max_concurrent.max(1);
+            self.commit(&state);
+        }
+        self.start();
+    }
+
+    /// 在并发上限内启动等待中的任务
+    pub fn start(&self) {
+        let started: Vec<(u64, QueueJobKind, DownloadOptions)> = {
+            let mut state = self.inner.state.lock().unwrap();
+            let running = state
+                .items
+                .iter()
+                .filter(|item| item.status == QueueStatus::Running)
+                .count();
+            let slots = state.max_concurrent.saturating_sub(running);
+            let started: Vec<_> = state
+                .items
+                .iter_mut()
+                .filter(|item| item.status == QueueStatus::Pending)
+                .take(slots)
+                .map(|item| {
+                    item.status = QueueStatus::Running;
+                    (item.job_id, item.kind, item.options.clone())
+                })
+                .collect();
+            if !started.is_empty() {
+                self.commit(&state);
+            }
+            started
+        };
+
+        for (job_id, kind, options) in started {
+            let queue = self.clone();
+            tauri::async_runtime::spawn(async move {
+                let result = queue.run(kind, options).await;
+                queue.finish(job_id, result);
+            });
+        }
+    }
+
+    async fn run(&self, kind: QueueJobKind, options: DownloadOptions) -> Result<DownloadResult> {
+        let app_handle = &self.inner.app_handle;
+        let downloader = match kind {
+            QueueJobKind::Download => Downloader::for_app(app_handle)?,
+            QueueJobKind::Update => Downloader::fresh_for_app(app_handle)?,
+        };
+        let registry = app_handle.state::<DownloadRegistry>();
+        let control = registry.register(&options.book_id)?;
+
+        let book_id = options.book_id.clone();
+        let downloader = downloader.with_control(control.clone());
+        let result = match kind {
+            QueueJobKind::Download => downloader.download(options, app_handle).await,
+            QueueJobKind::Update => downloader
+                .update(&book_id, app_handle)
+                .await
+                .map(|update| update.result),
+        };
+        registry.release(&book_id, &control);
+        result
+    }
+
+    fn finish(&self, job_id: u64, result: Result<DownloadResult>) {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            // 任务可能已被移出队列，或被同一本书的新任务取代
+            if let Some(item) = state.items.iter_mut().find(|item| item.job_id == job_id) {
+                match result {
+                    Ok(result) => {
+                        item.status = match result.status {
+                            DownloadStatus::Completed => QueueStatus::Completed,
+                            DownloadStatus::Failed => QueueStatus::Failed,
+                            DownloadStatus::Cancelled => QueueStatus::Cancelled,
+                        };
+                        item.file_path = result.file_path;
+                        item.error = result.error;
+                        item.failed_chapters = result.failed_chapters;
+                    }
+                    Err(e) => {
+                        item.status = QueueStatus::Failed;
+                        item.error = Some(e.to_string());
+                    }
+                }
+            }
+            self.commit(&state);
+        }
+        self.start();
+    }
+
+    /// 写回磁盘并通知前端
+    fn commit(&self, state: &QueueState) {
+        let saved = serde_json::to_vec_pretty(state)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| write_durable(&self.inner.path, &bytes));
+        if let Err(e) = saved {
+            eprintln!("下载队列保存失败: {}", e);
+        }
+        let _ = self.inner.app_handle.emit("queue-changed", &state.items);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::error::AppError;
+use crate::limiter::DEFAULT_RATE;
+use crate::network::validate_proxy;
+use crate::paths::write_durable;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// API 节点信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSource {
+    pub name: String,
+    pub base_url: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// 每秒最多请求数，未设置时使用默认限速
+    #[serde(default)]
+    pub rate_limit: Option<f64>,
+    /// 该节点单独使用的代理，优先于全局代理；`direct` 表示直连
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// 内置的默认节点，按优先级排列
+fn default_sources() -> Vec<ApiSource> {
+    [
+        ("中国|浙江省|宁波市|电信", "http://qkfqapi.vv9v.cn"),
+        ("中国|北京市|腾讯云", "http://49.232.137.12"),
+        ("备用节点", "http://43.248.77.205:22222"),
+        ("日本|东京", "https://fq.shusan.cn"),
+    ]
+    .into_iter()
+    .map(|(name, base_url)| ApiSource {
+        name: name.to_string(),
+        base_url: base_url.to_string(),
+        enabled: true,
+        rate_limit: None,
+        proxy: None,
+    })
+    .collect()
+}
+
+/// 把无法解析的配置文件改名为 `<文件名>.bak`
+fn backup_corrupt(path: &Path) {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    if let Err(e) = fs::rename(path, &backup) {
+        eprintln!("备份损坏的节点配置失败: {}", e);
+    }
+}
+
+/// API 节点配置
+///
+/// API 客户端和前端共用同一份列表，修改后立即生效；通过 [`SourceStore::load`]
+/// 打开时每次修改都会写回磁盘。
+#[derive(Debug, Clone)]
+pub struct SourceStore {
+    path: Option<PathBuf>,
+    sources: Arc<RwLock<Vec<ApiSource>>>,
+}
+
+impl SourceStore {
+    /// 仅在内存中使用给定的节点，不写入磁盘
+    pub fn new(sources: Vec<ApiSource>) -> Self {
+        Self {
+            path: None,
+            sources: Arc::new(RwLock::new(sources)),
+        }
+    }
+
+    /// 从磁盘读取节点配置，文件不存在或损坏时使用默认节点
+    ///
+    /// 损坏的文件改名为 `.bak` 保留，之后保存配置时不会覆盖用户手动编辑的内容。
+    pub fn load(path: PathBuf) -> Self {
+        let sources = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                eprintln!("节点配置 {} 无法解析，使用默认节点: {}", path.display(), e);
+                backup_corrupt(&path);
+                default_sources()
+            }),
+            Err(_) => default_sources(),
+        };
+        Self {
+            path: Some(path),
+            sources: Arc::new(RwLock::new(sources)),
+        }
+    }
+
+    pub fn list(&self) -> Vec<ApiSource> {
+        self.sources.read().unwrap().clone()
+    }
+
+    /// 已启用节点的地址，按优先级排列
+    pub fn enabled_urls(&self) -> Vec<String> {
+        self.sources
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|source| source.enabled)
+            .map(|source| source.base_url.clone())
+            .collect()
+    }
+
+    /// 节点的每秒请求数，手动编辑配置文件写入的无效值按默认限速处理
+    pub fn rate_limit(&self, base_url: &str) -> f64 {
+        self.sources
+            .read()
+            .unwrap()
+            .iter()
+            .find(|source| source.base_url == base_url)
+            .and_then(|source| source.rate_limit)
+            .filter(|rate| valid_rate(*rate))
+            .unwrap_or(DEFAULT_RATE)
+    }
+
+    /// 节点单独设置的代理
+    pub fn proxy(&self, base_url: &str) -> Option<String> {
+        self.sources
+            .read()
+            .unwrap()
+            .iter()
+            .find(|source| source.base_url == base_url)
+            .and_then(|source| source.proxy.clone())
+    }
+
+    /// 添加节点，排在列表最后
+    pub fn add(&self, name: &str, base_url: &str) -> Result<()> {
+        let base_url = base_url.trim().trim_end_matches('/');
+        if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+            return Err(AppError::invalid_input("节点地址必须以 http:// 或 https:// 开头").into());
+        }
+
+        let mut sources = self.sources.write().unwrap();
+        if sources.iter().any(|source| source.base_url == base_url) {
+            return Err(AppError::already_exists("该节点已存在").into());
+        }
+        let name = name.trim();
+        sources.push(ApiSource {
+            name: if name.is_empty() { base_url } else { name }.to_string(),
+            base_url: base_url.to_string(),
+            enabled: true,
+            rate_limit: None,
+            proxy: None,
+        });
+        self.commit(&sources)
+    }
+
+    pub fn remove(&self, base_url: &str) -> Result<()> {
+        let mut sources = self.sources.write().unwrap();
+        let pos = position(&sources, base_url)?;
+        sources.remove(pos);
+        self.commit(&sources)
+    }
+
+    pub fn set_enabled(&self, base_url: &str, enabled: bool) -> Result<()> {
+        let mut sources = self.sources.write().unwrap();
+        let pos = position(&sources, base_url)?;
+        sources[pos].enabled = enabled;
+        self.commit(&sources)
+    }
+
+    /// 设置节点的每秒请求数，`None` 恢复默认限速
+    pub fn set_rate_limit(&self, base_url: &str, rate_limit: Option<f64>) -> Result<()> {
+        if rate_limit.is_some_and(|rate| !valid_rate(rate)) {
+            return Err(AppError::invalid_input("每秒请求数必须大于 0").into());
+        }
+        let mut sources = self.sources.write().unwrap();
+        let pos = position(&sources, base_url)?;
+        sources[pos].rate_limit = rate_limit;
+        self.commit(&sources)
+    }
+
+    /// 设置节点单独使用的代理，`None` 恢复使用全局代理
+    pub fn set_proxy(&self, base_url: &str, proxy: Option<String>) -> Result<()> {
+        let proxy = proxy.map(|proxy| proxy.trim().to_string()).filter(|proxy| !proxy.is_empty());
+        if let Some(proxy) = &proxy {
+            validate_proxy(proxy)?;
+        }
+        let mut sources = self.sources.write().unwrap();
+        let pos = position(&sources, base_url)?;
+        sources[pos].proxy = proxy;
+        self.commit(&sources)
+    }
+
+    /// 按给定的地址顺序重排节点，未列出的节点保持原有顺序排在最后
+    pub fn reorder(&self, base_urls: &[String]) -> Result<()> {
+        let mut sources = self.sources.write().unwrap();
+        let mut rest = std::mem::take(&mut *sources);
+        for base_url in base_urls {
+            if let Some(pos) = rest.iter().position(|source| &source.base_url == base_url) {
+                sources.push(rest.remove(pos));
+            }
+        }
+        sources.extend(rest);
+        self.commit(&sources)
+    }
+
+    fn commit(&self, sources: &[ApiSource]) -> Result<()> {
+        if let Some(path) = &self.path {
+            write_durable(path, &serde_json::to_vec_pretty(sources)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for SourceStore {
+    /// 仅在内存中使用默认节点，不写入磁盘
+    fn default() -> Self {
+        Self::new(default_sources())
+    }
+}
+
+fn valid_rate(rate: f64) -> bool {
+    rate > 0.0 && rate.is_finite()
+}
+
+fn position(sources: &[ApiSource], base_url: &str) -> Result<usize> {
+    sources
+        .iter()
+        .position(|source| source.base_url == base_url)
+        .ok_or_else(|| AppError::not_found(format!("没有该节点: {}", base_url)).into())
+}
@@ -49,13 +49,43 @@ pub struct DownloadProgress {
     pub book_id: String,
 }
 
+/// 下载状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// 下载失败的章节
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedChapter {
+    pub id: String,
+    pub title: String,
+    pub index: usize,
+    pub error: String,
+}
+
 /// 下载结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadResult {
     pub success: bool,
+    pub status: DownloadStatus,
     pub file_path: Option<String>,
     pub error: Option<String>,
     pub book_name: String,
+    /// 重试后仍然失败的章节
+    pub failed_chapters: Vec<FailedChapter>,
+}
+
+/// 增量更新结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateResult {
+    /// 本次发现的新章节数
+    pub new_chapters: usize,
+    #[serde(flatten)]
+    pub result: DownloadResult,
 }
 
 /// 下载选项
@@ -66,6 +96,32 @@ pub struct DownloadOptions {
     pub format: String, // "txt" or "epub"
     pub start_chapter: Option<usize>,
     pub end_chapter: Option<usize>,
+    /// 普通模式下同时进行的章节请求数
+    pub concurrency: Option<usize>,
+    /// 失败章节的自动重试轮数
+    pub retry_rounds: Option<u32>,
+    /// 重试后仍缺失章节时的处理方式
+    pub missing_chapters: Option<MissingChapterPolicy>,
+}
+
+/// 离线导出选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    pub start_chapter: Option<usize>,
+    pub end_chapter: Option<usize>,
+    /// 本地没有内容的章节如何处理，默认导出失败
+    pub missing_chapters: Option<MissingChapterPolicy>,
+}
+
+/// 缺失章节的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingChapterPolicy {
+    /// 用占位文本代替缺失章节
+    #[default]
+    Placeholder,
+    /// 整个下载视为失败
+    Fail,
 }
 
 /// API 响应包装
@@ -79,6 +135,7 @@ pub struct ApiResponse<T> {
 /// 目录项
 #[derive(Debug, Clone, Deserialize)]
 pub struct DirectoryItem {
+    #[serde(alias = "itemId")]
     pub item_id: String,
     pub title: String,
 }
@@ -89,17 +146,145 @@ pub struct DirectoryData {
     pub lists: Vec<DirectoryItem>,
 }
 
+/// `/api/book` 响应中的目录数据
+#[derive(Debug, Default, Deserialize)]
+pub struct BookDirectoryData {
+    /// 按卷分组的章节
+    #[serde(rename = "chapterListWithVolume", default)]
+    pub volumes: Vec<Vec<DirectoryItem>>,
+    /// 没有分卷信息时只有章节 id
+    #[serde(rename = "allItemIds", default)]
+    pub item_ids: Vec<String>,
+}
+
+/// `/api/book` 响应数据
+#[derive(Debug, Deserialize)]
+pub struct BookApiData {
+    #[serde(default)]
+    pub data: BookDirectoryData,
+}
+
+/// 接口返回的书籍数据，字段名和类型因节点版本而异
+#[derive(Debug, Deserialize)]
+pub struct BookData {
+    pub book_name: String,
+    pub author: String,
+    pub thumb_url: Option<String>,
+    pub cover_url: Option<String>,
+    #[serde(rename = "abstract", default)]
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "lenient_i64")]
+    pub word_number: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_i64")]
+    pub word_count: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_i64")]
+    pub serial_count: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_i64")]
+    pub chapter_number: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_i64")]
+    pub chapter_count: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub category: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub creation_status: Option<String>,
+}
+
+impl BookData {
+    /// 转换为前端使用的书籍信息，`book_id` 以请求参数为准
+    pub fn into_book_info(self, book_id: String) -> BookInfo {
+        BookInfo {
+            book_id,
+            book_name: self.book_name,
+            author: self.author,
+            cover_url: self.thumb_url.or(self.cover_url).unwrap_or_default(),
+            description: self.description.unwrap_or_default(),
+            word_count: self.word_number.or(self.word_count),
+            chapter_count: self
+                .serial_count
+                .or(self.chapter_number)
+                .or(self.chapter_count),
+            category: self.category,
+            status: self.creation_status,
+        }
+    }
+}
+
+/// 搜索响应数据
+#[derive(Debug, Deserialize)]
+pub struct SearchData {
+    #[serde(default)]
+    pub search_tabs: Vec<SearchTab>,
+}
+
+/// 搜索结果分类
+#[derive(Debug, Deserialize)]
+pub struct SearchTab {
+    /// 条目结构不固定，书籍数据可能在 `book_data` 中，也可能就是条目本身
+    #[serde(default)]
+    pub data: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// 搜索结果条目，书籍数据中的 `book_id` 也按此结构读取
+#[derive(Debug, Deserialize)]
+pub struct SearchItem {
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub book_id: Option<String>,
+    #[serde(default)]
+    pub book_data: Vec<serde_json::Value>,
+}
+
 /// 书籍详情响应内层数据
 #[derive(Debug, Deserialize)]
 pub struct BookDetailInner {
-    pub code: Option<i32>,
     pub message: Option<String>,
-    pub data: Option<BookInfo>,
+    pub data: Option<BookData>,
 }
 
 /// 内容响应
 #[derive(Debug, Deserialize)]
 pub struct ContentResponse {
     pub content: Option<String>,
-    pub title: Option<String>,
+}
+
+/// 单章内容响应数据，部分节点直接返回正文字符串
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ContentData {
+    Text(String),
+    Content(ContentResponse),
+}
+
+/// 批量内容响应中的章节
+#[derive(Debug, Deserialize)]
+pub struct BatchContentItem {
+    #[serde(alias = "itemId")]
+    pub item_id: String,
+    pub content: Option<String>,
+}
+
+/// 批量内容响应数据
+#[derive(Debug, Deserialize)]
+pub struct BatchContentData {
+    #[serde(default)]
+    pub lists: Vec<BatchContentItem>,
+}
+
+/// 接受字符串或数字
+fn lenient_string<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Option<String>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(de)? {
+        Some(serde_json::Value::String(s)) => Some(s),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// 接受数字或数字字符串
+fn lenient_i64<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Option<i64>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(de)? {
+        Some(serde_json::Value::Number(n)) => n.as_i64(),
+        Some(serde_json::Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    })
 }
@@ -0,0 +1,124 @@
+use crate::book_source::{self, BookSource};
+use crate::library::{FollowedBook, Library};
+use crate::queue::DownloadQueue;
+use crate::types::Chapter;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 自动检查更新的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// 启动后首次检查前的等待时间，避免与启动时的其他请求争抢
+const INITIAL_DELAY: Duration = Duration::from_secs(60);
+
+/// 关注书籍的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookUpdate {
+    pub book_id: String,
+    pub book_name: String,
+    pub old_chapter_count: usize,
+    pub new_chapter_count: usize,
+    /// 新增的章节
+    pub new_chapters: Vec<Chapter>,
+    pub old_status: Option<String>,
+    pub new_status: Option<String>,
+    /// 是否已自动加入增量更新队列
+    pub enqueued: bool,
+}
+
+/// 启动后台定时检查
+pub fn spawn_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(INITIAL_DELAY).await;
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = check_followed(&app_handle).await {
+                eprintln!("检查关注书籍更新失败: {}", e);
+            }
+        }
+    });
+}
+
+/// 检查所有关注的书籍，每本有变化的书籍都会发送 `book-updated` 事件
+pub async fn check_followed(app_handle: &AppHandle) -> Result<Vec<BookUpdate>> {
+    let library = app_handle.state::<Library>().inner().clone();
+    let source = book_source::fresh_for_app(app_handle);
+    let mut updates = Vec::new();
+
+    for book in library.followed()? {
+        match check_book(source.as_ref(), &library, &book).await {
+            Ok(Some(mut update)) => {
+                if book.auto_download && !update.new_chapters.is_empty() {
+                    update.enqueued = enqueue_update(app_handle, &library, &book.book_id);
+                }
+                let _ = app_handle.emit("book-updated", &update);
+                updates.push(update);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("检查《{}》更新失败: {}", book.book_name, e),
+        }
+    }
+
+    Ok(updates)
+}
+
+/// 与上次检查的快照比较，没有变化时返回 `None`
+async fn check_book(
+    source: &dyn BookSource,
+    library: &Library,
+    book: &FollowedBook,
+) -> Result<Option<BookUpdate>> {
+    let info = source.get_book_detail(&book.book_id).await?;
+    let chapters = source.get_directory(&book.book_id).await?;
+
+    // 按章节 id 比较，删除、调整顺序或替换章节时也能找出真正新增的章节；
+    // 旧版本关注时没有保存章节清单，只能按章节数比较
+    let known = library.follow_chapter_ids(&book.book_id)?;
+    let new_chapters: Vec<Chapter> = if known.is_empty() {
+        chapters.iter().skip(book.chapter_count).cloned().collect()
+    } else {
+        chapters
+            .iter()
+            .filter(|ch| !known.contains(&ch.id))
+            .cloned()
+            .collect()
+    };
+
+    library.update_follow(&book.book_id, &info.book_name, &chapters, info.status.as_deref())?;
+    library.set_chapter_count(&book.book_id, chapters.len())?;
+
+    if new_chapters.is_empty()
+        && chapters.len() == book.chapter_count
+        && info.status == book.status
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(BookUpdate {
+        book_id: book.book_id.clone(),
+        book_name: info.book_name,
+        old_chapter_count: book.chapter_count,
+        new_chapter_count: chapters.len(),
+        new_chapters,
+        old_status: book.status.clone(),
+        new_status: info.status,
+        enqueued: false,
+    }))
+}
+
+/// 把书籍加入增量更新队列，只有书库中下载过的书籍才能增量更新
+fn enqueue_update(app_handle: &AppHandle, library: &Library, book_id: &str) -> bool {
+    let entry = match library.get(book_id) {
+        Ok(Some(entry)) => entry,
+        _ => return false,
+    };
+    match app_handle.state::<DownloadQueue>().enqueue_update(entry.options) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("加入更新队列失败: {}", e);
+            false
+        }
+    }
+}
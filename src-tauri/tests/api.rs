@@ -0,0 +1,263 @@
+//! 使用本地模拟节点测试 API 客户端，不访问真实的第三方节点
+
+mod common;
+
+use common::{api, fixture, mount, BOOK_ID, DEAD_NODE};
+use tomato_novel_manager_lib::error::{AppError, FallbackError};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn search_reads_book_data_and_inline_books() {
+    let server = MockServer::start().await;
+    mount(&server, "/api/search", fixture("search")).await;
+
+    let result = api(&[&server.uri()]).search_books("星海", 0).await.unwrap();
+
+    assert!(result.has_more);
+    assert_eq!(result.total, 2);
+    let book = &result.books[0];
+    assert_eq!(book.book_id, BOOK_ID);
+    assert_eq!(book.book_name, "星海归途");
+    assert_eq!(book.word_count, Some(1234567));
+    assert_eq!(book.chapter_count, Some(512));
+    assert!(book.cover_url.contains("thumb"));
+
+    // 条目本身就是书籍数据，book_id 和数字字段为数字类型
+    let book = &result.books[1];
+    assert_eq!(book.book_id, "7208476543210987654");
+    assert_eq!(book.chapter_count, Some(300));
+    assert_eq!(book.status.as_deref(), Some("0"));
+    assert!(book.cover_url.contains("cover"));
+}
+
+/// 在搜索结果的书籍列表最前面插入一个条目
+fn search_with(item: serde_json::Value) -> ResponseTemplate {
+    let mut body: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/search.json")).unwrap();
+    body["data"]["search_tabs"][1]["data"]
+        .as_array_mut()
+        .unwrap()
+        .insert(0, item);
+    ResponseTemplate::new(200).set_body_json(body)
+}
+
+#[tokio::test]
+async fn malformed_search_item_is_skipped() {
+    let server = MockServer::start().await;
+    // 有 book_id 但缺少书名
+    let bad = serde_json::json!({ "book_id": "1", "author": "无名" });
+    mount(&server, "/api/search", search_with(bad)).await;
+
+    let result = api(&[&server.uri()]).search_books("星海", 0).await.unwrap();
+
+    assert_eq!(result.total, 2);
+    assert_eq!(result.books[0].book_id, BOOK_ID);
+}
+
+#[tokio::test]
+async fn detail_reads_nested_data() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/detail"))
+        .and(query_param("book_id", BOOK_ID))
+        .respond_with(fixture("detail"))
+        .mount(&server)
+        .await;
+
+    let book = api(&[&server.uri()]).get_book_detail(BOOK_ID).await.unwrap();
+
+    assert_eq!(book.book_id, BOOK_ID);
+    assert_eq!(book.author, "青衫客");
+    assert_eq!(book.chapter_count, Some(3));
+    assert_eq!(book.status.as_deref(), Some("1"));
+}
+
+#[tokio::test]
+async fn book_remove_stops_fallback() {
+    let removed = MockServer::start().await;
+    mount(&removed, "/api/detail", fixture("detail_removed")).await;
+    let other = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/detail"))
+        .respond_with(fixture("detail"))
+        .expect(0)
+        .mount(&other)
+        .await;
+
+    let err = api(&[&removed.uri(), &other.uri()])
+        .get_book_detail(BOOK_ID)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BookRemoved)));
+}
+
+#[tokio::test]
+async fn malformed_detail_is_reported_as_schema_change() {
+    let server = MockServer::start().await;
+    mount(&server, "/api/detail", fixture("detail_malformed")).await;
+
+    let err = api(&[&server.uri()]).get_book_detail(BOOK_ID).await.unwrap_err();
+
+    let err = AppError::from(err);
+    assert_eq!(err.code(), "schema_changed");
+    assert!(err.to_string().contains("book_name"));
+}
+
+#[tokio::test]
+async fn non_json_response_is_recorded_per_node() {
+    let server = MockServer::start().await;
+    mount(
+        &server,
+        "/api/detail",
+        ResponseTemplate::new(200).set_body_string("<html>维护中</html>"),
+    )
+    .await;
+
+    let err = api(&[&server.uri()]).get_book_detail(BOOK_ID).await.unwrap_err();
+
+    let fallback = err.downcast_ref::<FallbackError>().unwrap();
+    assert_eq!(fallback.attempts.len(), 1);
+    assert_eq!(fallback.attempts[0].status, Some(200));
+    assert!(fallback.attempts[0].error.contains("JSON"));
+}
+
+#[tokio::test]
+async fn falls_back_past_failing_nodes() {
+    let broken = MockServer::start().await;
+    mount(&broken, "/api/detail", ResponseTemplate::new(502)).await;
+    let healthy = MockServer::start().await;
+    mount(&healthy, "/api/detail", fixture("detail")).await;
+
+    let book = api(&[DEAD_NODE, &broken.uri(), &healthy.uri()])
+        .get_book_detail(BOOK_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(book.book_name, "星海归途");
+}
+
+#[tokio::test]
+async fn all_nodes_failing_lists_every_attempt() {
+    let broken = MockServer::start().await;
+    mount(&broken, "/api/detail", ResponseTemplate::new(503)).await;
+
+    let err = api(&[&broken.uri(), DEAD_NODE])
+        .get_book_detail(BOOK_ID)
+        .await
+        .unwrap_err();
+
+    let fallback = err.downcast_ref::<FallbackError>().unwrap();
+    let statuses: Vec<_> = fallback.attempts.iter().map(|a| a.status).collect();
+    assert_eq!(statuses, vec![Some(503), None]);
+    assert_eq!(fallback.attempts[1].base_url, DEAD_NODE);
+    assert!(fallback.attempts.iter().all(|a| !a.skipped));
+}
+
+#[tokio::test]
+async fn directory_uses_directory_api() {
+    let server = MockServer::start().await;
+    mount(&server, "/api/directory", fixture("directory")).await;
+
+    let chapters = api(&[&server.uri()]).get_directory(BOOK_ID).await.unwrap();
+
+    assert_eq!(chapters.len(), 3);
+    assert_eq!(chapters[1].id, "7143038700002");
+    assert_eq!(chapters[1].title, "第2章 星门");
+    assert_eq!(chapters[2].index, 2);
+}
+
+#[tokio::test]
+async fn directory_falls_back_to_book_api() {
+    let server = MockServer::start().await;
+    mount(&server, "/api/directory", fixture("directory_error")).await;
+    mount(&server, "/api/book", fixture("book")).await;
+
+    let chapters = api(&[&server.uri()]).get_directory(BOOK_ID).await.unwrap();
+
+    // itemId 和 item_id 两种写法都能识别，分卷按顺序展开
+    let ids: Vec<_> = chapters.iter().map(|ch| ch.id.as_str()).collect();
+    assert_eq!(ids, vec!["7143038700001", "7143038700002", "7143038700003"]);
+    assert_eq!(chapters[2].title, "第3章 归途");
+    assert_eq!(chapters[2].index, 2);
+}
+
+#[tokio::test]
+async fn book_api_without_volumes_uses_item_ids() {
+    let server = MockServer::start().await;
+    mount(&server, "/api/directory", fixture("directory_error")).await;
+    mount(&server, "/api/book", fixture("book_ids_only")).await;
+
+    let chapters = api(&[&server.uri()]).get_directory(BOOK_ID).await.unwrap();
+
+    assert_eq!(chapters.len(), 2);
+    assert_eq!(chapters[1].title, "第2章");
+}
+
+#[tokio::test]
+async fn chapter_content_is_cleaned() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/content"))
+        .and(query_param("tab", "小说"))
+        .and(query_param("item_id", "7143038700001"))
+        .respond_with(fixture("content"))
+        .mount(&server)
+        .await;
+
+    let content = api(&[&server.uri()])
+        .get_chapter_content("7143038700001")
+        .await
+        .unwrap();
+
+    assert_eq!(content, "夜色沉沉，星光洒落在港口。\n\n少年背起行囊。\n\n他回头望了一眼。");
+}
+
+#[tokio::test]
+async fn chapter_content_accepts_plain_string() {
+    let server = MockServer::start().await;
+    mount(&server, "/api/content", fixture("content_text")).await;
+
+    let content = api(&[&server.uri()])
+        .get_chapter_content("7143038700002")
+        .await
+        .unwrap();
+
+    assert_eq!(content, "第二章的正文。");
+}
+
+#[tokio::test]
+async fn incomplete_fast_mode_leaves_missing_chapters_for_normal_mode() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/content"))
+        .and(query_param("tab", "批量"))
+        .respond_with(fixture("content_batch_partial"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/content"))
+        .and(query_param("tab", "小说"))
+        .and(query_param("item_id", "7143038700003"))
+        .respond_with(fixture("content_text"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount(&server, "/api/directory", fixture("directory")).await;
+
+    let api = api(&[&server.uri()]);
+    let chapters = api.get_directory(BOOK_ID).await.unwrap();
+    let content_map = api.get_full_content(BOOK_ID).await.unwrap();
+    assert_eq!(content_map.len(), 2);
+    assert_eq!(content_map["7143038700001"], "第一章正文。");
+
+    // 批量结果中缺失的章节逐章补齐
+    let missing: Vec<_> = chapters
+        .iter()
+        .filter(|ch| !content_map.contains_key(&ch.id))
+        .collect();
+    assert_eq!(missing.len(), 1);
+    let content = api.get_chapter_content(&missing[0].id).await.unwrap();
+    assert_eq!(content, "第二章的正文。");
+}
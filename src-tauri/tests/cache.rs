@@ -0,0 +1,137 @@
+//! 响应缓存和章节存储
+
+mod common;
+
+use common::{api, fixture, mount, BOOK_ID};
+use std::sync::Arc;
+use tomato_novel_manager_lib::book_source::BookSource;
+use tomato_novel_manager_lib::cache::{CacheKind, CachedSource, ResponseCache};
+use tomato_novel_manager_lib::chapter_store::ChapterStore;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer};
+
+async fn mock_detail(calls: u64) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/detail"))
+        .respond_with(fixture("detail"))
+        .expect(calls)
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn repeated_lookups_use_cache() {
+    let server = mock_detail(1).await;
+    let source = CachedSource::new(Arc::new(api(&[&server.uri()])), ResponseCache::default());
+
+    let first = source.get_book_detail(BOOK_ID).await.unwrap();
+    let second = source.get_book_detail(BOOK_ID).await.unwrap();
+
+    assert_eq!(first.book_name, second.book_name);
+}
+
+#[tokio::test]
+async fn bypass_refreshes_cache() {
+    let server = mock_detail(2).await;
+    let api = Arc::new(api(&[&server.uri()]));
+    let cache = ResponseCache::default();
+
+    CachedSource::new(api.clone(), cache.clone())
+        .get_book_detail(BOOK_ID)
+        .await
+        .unwrap();
+    let fresh = CachedSource::new(api.clone(), cache.clone()).bypass(true);
+    fresh.get_book_detail(BOOK_ID).await.unwrap();
+
+    // 跳过缓存得到的结果同样写入缓存，之后不再请求
+    CachedSource::new(api, cache)
+        .get_book_detail(BOOK_ID)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn disk_cache_survives_restart() {
+    let dir = std::env::temp_dir().join(format!("tomato-cache-{}", std::process::id()));
+    let server = mock_detail(1).await;
+    let api = Arc::new(api(&[&server.uri()]));
+
+    CachedSource::new(api.clone(), ResponseCache::open(&dir))
+        .get_book_detail(BOOK_ID)
+        .await
+        .unwrap();
+    let book = CachedSource::new(api.clone(), ResponseCache::open(&dir))
+        .get_book_detail(BOOK_ID)
+        .await
+        .unwrap();
+    assert_eq!(book.book_id, BOOK_ID);
+
+    ResponseCache::open(&dir).clear().unwrap();
+    assert!(!dir.exists());
+}
+
+#[test]
+fn disk_cache_hashes_long_keys_and_prunes_obsolete_files() {
+    let dir = std::env::temp_dir().join(format!("tomato-cache-prune-{}", std::process::id()));
+    // 超过文件名长度限制的搜索关键字也能缓存
+    let key = "星海归途".repeat(100);
+    ResponseCache::open(&dir).put(CacheKind::Search, &key, &vec![1, 2, 3]);
+    let cached: Option<Vec<i32>> = ResponseCache::open(&dir).get(CacheKind::Search, &key);
+    assert_eq!(cached, Some(vec![1, 2, 3]));
+
+    // 早已过期和损坏的文件在打开时删除
+    let old = dir.join("detail").join("old.json");
+    let broken = dir.join("detail").join("broken.json");
+    std::fs::create_dir_all(old.parent().unwrap()).unwrap();
+    std::fs::write(&old, r#"{"stored_at":0,"value":{}}"#).unwrap();
+    std::fs::write(&broken, "{").unwrap();
+    ResponseCache::open(&dir);
+    assert!(!old.exists());
+    assert!(!broken.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn chapters_are_fetched_once_and_shared() {
+    let dir = std::env::temp_dir().join(format!("tomato-chapters-{}", std::process::id()));
+    let server = MockServer::start().await;
+    mount(&server, "/api/directory", fixture("directory")).await;
+    Mock::given(method("GET"))
+        .and(path("/api/content"))
+        .and(query_param("tab", "批量"))
+        .respond_with(fixture("content_batch_partial"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/content"))
+        .and(query_param("tab", "小说"))
+        .respond_with(fixture("content_text"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let api = Arc::new(api(&[&server.uri()]));
+    let source = || {
+        CachedSource::new(api.clone(), ResponseCache::default())
+            .with_chapters(ChapterStore::new(&dir))
+    };
+
+    // 第一次下载：批量接口缺第 3 章，逐章补齐
+    let content_map = source().get_full_content(BOOK_ID).await.unwrap();
+    assert_eq!(content_map.len(), 2);
+    source().get_chapter_content("7143038700003").await.unwrap();
+
+    // 再次导出时全部章节来自本地存储
+    let content_map = source().get_full_content(BOOK_ID).await.unwrap();
+    assert_eq!(content_map.len(), 3);
+    assert_eq!(content_map["7143038700001"], "第一章正文。");
+    assert_eq!(
+        source().get_chapter_content("7143038700003").await.unwrap(),
+        "第二章的正文。"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,60 @@
+//! 集成测试共用的模拟节点和测试数据
+
+use tomato_novel_manager_lib::api::FanqieApi;
+use tomato_novel_manager_lib::sources::{ApiSource, SourceStore};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub const BOOK_ID: &str = "7143038691944959011";
+
+/// 无人监听的地址，请求会立即被拒绝
+#[allow(dead_code)]
+pub const DEAD_NODE: &str = "http://127.0.0.1:9";
+
+pub fn fixture(name: &str) -> ResponseTemplate {
+    let body = match name {
+        "search" => include_str!("../fixtures/search.json"),
+        "detail" => include_str!("../fixtures/detail.json"),
+        "detail_removed" => include_str!("../fixtures/detail_removed.json"),
+        "detail_malformed" => include_str!("../fixtures/detail_malformed.json"),
+        "directory" => include_str!("../fixtures/directory.json"),
+        "directory_error" => include_str!("../fixtures/directory_error.json"),
+        "book" => include_str!("../fixtures/book.json"),
+        "book_ids_only" => include_str!("../fixtures/book_ids_only.json"),
+        "content" => include_str!("../fixtures/content.json"),
+        "content_text" => include_str!("../fixtures/content_text.json"),
+        "content_batch_partial" => include_str!("../fixtures/content_batch_partial.json"),
+        _ => panic!("未知的测试数据: {}", name),
+    };
+    ResponseTemplate::new(200).set_body_raw(body, "application/json")
+}
+
+/// 依次使用给定节点的客户端
+pub fn api(nodes: &[&str]) -> FanqieApi {
+    FanqieApi::with_sources(sources(nodes))
+}
+
+/// 只在内存中保存的节点配置
+pub fn sources(nodes: &[&str]) -> SourceStore {
+    let sources = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, base_url)| ApiSource {
+            name: format!("节点{}", i + 1),
+            base_url: base_url.to_string(),
+            enabled: true,
+            rate_limit: None,
+            proxy: None,
+        })
+        .collect();
+    SourceStore::new(sources)
+}
+
+#[allow(dead_code)]
+pub async fn mount(server: &MockServer, endpoint: &str, response: ResponseTemplate) {
+    Mock::given(method("GET"))
+        .and(path(endpoint))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
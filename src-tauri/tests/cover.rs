@@ -0,0 +1,62 @@
+//! EPUB 封面的格式转换和生成
+
+use image::{ImageFormat, RgbImage};
+use std::io::Cursor;
+use tomato_novel_manager_lib::cover::Cover;
+use tomato_novel_manager_lib::types::BookInfo;
+
+fn encode(format: ImageFormat) -> Vec<u8> {
+    let mut data = Vec::new();
+    RgbImage::new(4, 4)
+        .write_to(&mut Cursor::new(&mut data), format)
+        .unwrap();
+    data
+}
+
+#[test]
+fn common_formats_are_kept_and_others_converted_to_jpeg() {
+    let png = encode(ImageFormat::Png);
+    let cover = Cover::from_image(png.clone()).unwrap();
+    assert_eq!(cover.mime_type, "image/png");
+    assert_eq!(cover.data, png);
+
+    let cover = Cover::from_image(encode(ImageFormat::Gif)).unwrap();
+    assert_eq!(cover.mime_type, "image/jpeg");
+    assert_eq!(cover.file_name, "cover.jpg");
+    assert_eq!(image::guess_format(&cover.data).unwrap(), ImageFormat::Jpeg);
+
+    assert!(Cover::from_image(b"<html>404</html>".to_vec()).is_err());
+}
+
+fn book(name: &str, author: &str) -> BookInfo {
+    BookInfo {
+        book_name: name.to_string(),
+        author: author.to_string(),
+        book_id: "1".to_string(),
+        cover_url: String::new(),
+        description: String::new(),
+        word_count: None,
+        chapter_count: None,
+        category: None,
+        status: None,
+    }
+}
+
+#[test]
+fn title_card_shows_escaped_title_and_author() {
+    let cover = Cover::title_card(&book("星海<归途>", "云 & 海"));
+
+    assert_eq!(cover.mime_type, "image/svg+xml");
+    assert_eq!(cover.file_name, "cover.svg");
+    let svg = String::from_utf8(cover.data).unwrap();
+    assert!(svg.contains("星海&lt;归途&gt;"));
+    assert!(svg.contains("云 &amp; 海"));
+}
+
+#[test]
+fn title_cards_differ_between_books() {
+    let first = Cover::title_card(&book("星海归途", "云海"));
+    let second = Cover::title_card(&book("长夜将明", "云海"));
+
+    assert_ne!(first.data, second.data);
+}
@@ -0,0 +1,317 @@
+//! 不依赖 Tauri 运行下载流程
+
+mod common;
+
+use common::{api, fixture, mount, BOOK_ID, DEAD_NODE};
+use image::{ImageFormat, RgbImage};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tomato_novel_manager_lib::cache::{CachedSource, ResponseCache};
+use tomato_novel_manager_lib::chapter_store::ChapterStore;
+use tomato_novel_manager_lib::checkpoint::CheckpointStore;
+use tomato_novel_manager_lib::control::{DownloadControl, DownloadRegistry};
+use tomato_novel_manager_lib::downloader::Downloader;
+use tomato_novel_manager_lib::library::Library;
+use tomato_novel_manager_lib::progress::{ChannelProgress, NoProgress};
+use tomato_novel_manager_lib::types::{
+    DownloadOptions, DownloadStatus, ExportOptions, MissingChapterPolicy,
+};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// 模拟一本三章的书：批量接口缺第 3 章，`chapter3` 决定单章接口对第 3 章的响应
+async fn mock_book(chapter3: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    // 封面也指向模拟节点，测试不访问外网
+    let detail = include_str!("fixtures/detail.json")
+        .replace("https://p3-novel.byteimg.com", &server.uri());
+    mount(
+        &server,
+        "/api/detail",
+        ResponseTemplate::new(200).set_body_raw(detail, "application/json"),
+    )
+    .await;
+    mount(
+        &server,
+        &format!("/thumb/{}.jpg", BOOK_ID),
+        ResponseTemplate::new(200).set_body_raw(png(), "image/png"),
+    )
+    .await;
+    mount(&server, "/api/directory", fixture("directory")).await;
+    Mock::given(method("GET"))
+        .and(path("/api/content"))
+        .and(query_param("tab", "批量"))
+        .respond_with(fixture("content_batch_partial"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/content"))
+        .and(query_param("tab", "小说"))
+        .and(query_param("item_id", "7143038700003"))
+        .respond_with(chapter3)
+        .mount(&server)
+        .await;
+    server
+}
+
+fn png() -> Vec<u8> {
+    let mut data = Vec::new();
+    RgbImage::new(2, 3)
+        .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+        .unwrap();
+    data
+}
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tomato-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn options(name: &str) -> DownloadOptions {
+    DownloadOptions {
+        book_id: BOOK_ID.to_string(),
+        save_path: temp_dir().join(name).to_string_lossy().to_string(),
+        format: "txt".to_string(),
+        start_chapter: None,
+        end_chapter: None,
+        concurrency: None,
+        retry_rounds: Some(0),
+        missing_chapters: None,
+    }
+}
+
+fn downloader(server: &MockServer) -> Downloader {
+    Downloader::with_source(Arc::new(api(&[&server.uri()])))
+}
+
+#[tokio::test]
+async fn incomplete_fast_mode_is_completed_in_normal_mode() {
+    let server = mock_book(fixture("content_text")).await;
+    let (progress, mut receiver) = ChannelProgress::new();
+
+    let options = options("fast-mode.txt");
+    let result = downloader(&server).download(options, &progress).await.unwrap();
+
+    assert_eq!(result.status, DownloadStatus::Completed);
+    assert!(result.failed_chapters.is_empty());
+    let text = std::fs::read_to_string(PathBuf::from(result.file_path.unwrap())).unwrap();
+    assert!(text.starts_with("星海归途"));
+    let first = text.find("第一章正文。").unwrap();
+    let third = text.find("第二章的正文。").unwrap();
+    assert!(first < third);
+
+    let mut messages = Vec::new();
+    while let Ok(p) = receiver.try_recv() {
+        assert_eq!(p.book_id, BOOK_ID);
+        messages.push(p.message);
+    }
+    assert!(messages.iter().any(|m| m.contains("极速模式内容不完整")));
+    assert_eq!(messages.last().map(String::as_str), Some("下载完成！"));
+}
+
+#[tokio::test]
+async fn missing_chapter_gets_placeholder() {
+    let server = mock_book(ResponseTemplate::new(500)).await;
+
+    let options = options("placeholder.txt");
+    let result = downloader(&server).download(options, &NoProgress).await.unwrap();
+
+    assert_eq!(result.status, DownloadStatus::Completed);
+    assert_eq!(result.failed_chapters.len(), 1);
+    assert_eq!(result.failed_chapters[0].id, "7143038700003");
+    let text = std::fs::read_to_string(result.file_path.unwrap()).unwrap();
+    assert!(text.contains("本章下载失败"));
+    // 错误详情只在 failed_chapters 中，不写进书里
+    assert!(!text.contains(&server.uri()));
+    assert!(!result.failed_chapters[0].error.is_empty());
+}
+
+#[tokio::test]
+async fn unknown_format_is_rejected() {
+    let server = mock_book(fixture("content_text")).await;
+
+    let options = DownloadOptions {
+        format: "pdf".to_string(),
+        ..options("unknown-format.pdf")
+    };
+    let save_path = options.save_path.clone();
+    let error = downloader(&server).download(options, &NoProgress).await.unwrap_err();
+
+    assert!(error.to_string().contains("pdf"));
+    assert!(!PathBuf::from(save_path).exists());
+}
+
+#[tokio::test]
+async fn missing_chapter_fails_download_when_required() {
+    let server = mock_book(ResponseTemplate::new(500)).await;
+
+    let options = DownloadOptions {
+        missing_chapters: Some(MissingChapterPolicy::Fail),
+        ..options("fail.txt")
+    };
+    let result = downloader(&server).download(options, &NoProgress).await.unwrap();
+
+    assert_eq!(result.status, DownloadStatus::Failed);
+    assert!(!result.success);
+    assert_eq!(result.failed_chapters.len(), 1);
+}
+
+#[tokio::test]
+async fn placeholder_chapter_is_replaced_on_update() {
+    let dir = temp_dir();
+    let library = Library::open(&dir.join("placeholder-update.db")).unwrap();
+    let server = mock_book(ResponseTemplate::new(500)).await;
+    let result = downloader(&server)
+        .with_library(library.clone())
+        .download(options("placeholder-update.txt"), &NoProgress)
+        .await
+        .unwrap();
+    assert_eq!(result.failed_chapters.len(), 1);
+    let entry = library.get(BOOK_ID).unwrap().unwrap();
+    assert_eq!(entry.downloaded_chapters, 2);
+
+    // 最后一章是占位文本，不能直接追加，否则同一章会出现两次
+    let server = mock_book(fixture("content_text")).await;
+    let update = downloader(&server)
+        .with_library(library.clone())
+        .update(BOOK_ID, &NoProgress)
+        .await
+        .unwrap();
+    assert_eq!(update.new_chapters, 1);
+    let text = std::fs::read_to_string(update.result.file_path.unwrap()).unwrap();
+    assert_eq!(text.matches("第3章 归途").count(), 1);
+    assert!(!text.contains("本章下载失败"));
+    assert!(library.missing_chapters(BOOK_ID).unwrap().is_empty());
+    // 重新生成文件时沿用检查更新时获取的详情和目录
+    assert_eq!(requests(&server, "/api/detail").await, 1);
+    assert_eq!(requests(&server, "/api/directory").await, 1);
+}
+
+#[tokio::test]
+async fn update_without_new_chapters_refreshes_library() {
+    let dir = temp_dir();
+    let library = Library::open(&dir.join("no-new-chapters.db")).unwrap();
+    let server = mock_book(fixture("content_text")).await;
+    downloader(&server)
+        .with_library(library.clone())
+        .download(options("no-new-chapters.txt"), &NoProgress)
+        .await
+        .unwrap();
+    library.set_chapter_count(BOOK_ID, 1).unwrap();
+
+    let update = downloader(&server)
+        .with_library(library.clone())
+        .update(BOOK_ID, &NoProgress)
+        .await
+        .unwrap();
+
+    assert_eq!(update.new_chapters, 0);
+    assert_eq!(library.get(BOOK_ID).unwrap().unwrap().chapter_count, 3);
+}
+
+
+#[test]
+fn finished_task_keeps_newer_registration() {
+    let registry = DownloadRegistry::default();
+    let old = registry.register(BOOK_ID).unwrap();
+    registry.release(BOOK_ID, &old);
+    let new = registry.register(BOOK_ID).unwrap();
+
+    // 旧任务再次注销时不影响同一本书的新任务
+    registry.release(BOOK_ID, &old);
+    assert!(Arc::ptr_eq(&registry.get(BOOK_ID).unwrap(), &new));
+}
+
+#[tokio::test]
+async fn cancel_interrupts_retry_backoff() {
+    let server = mock_book(ResponseTemplate::new(500)).await;
+    let control = Arc::new(DownloadControl::new());
+    let canceller = control.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        canceller.cancel();
+    });
+
+    // 轮数过大时被限制，不会溢出
+    let options = DownloadOptions {
+        retry_rounds: Some(u32::MAX),
+        ..options("cancel-retry.txt")
+    };
+    let started = Instant::now();
+    let result = downloader(&server)
+        .with_control(control)
+        .download(options, &NoProgress)
+        .await
+        .unwrap();
+
+    assert_eq!(result.status, DownloadStatus::Cancelled);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn downloaded_book_exports_offline() {
+    let server = mock_book(fixture("content_text")).await;
+    let dir = temp_dir();
+    let library = Library::open(&dir.join("export.db")).unwrap();
+    let store = ChapterStore::new(dir.join("export-chapters"));
+    let checkpoints = CheckpointStore::new(dir.join("export-checkpoints"));
+    let source = CachedSource::new(Arc::new(api(&[&server.uri()])), ResponseCache::default())
+        .with_chapters(store.clone());
+    Downloader::with_source(Arc::new(source))
+        .with_checkpoints(checkpoints.clone())
+        .with_chapters(store.clone())
+        .with_library(library.clone())
+        .download(options("export.txt"), &NoProgress)
+        .await
+        .unwrap();
+    // 章节只保存在章节存储中，下载完成后不保留断点
+    assert!(checkpoints.load(BOOK_ID).unwrap().is_empty());
+    // 封面也在下载时保存，导出时不再联网
+    drop(server);
+
+    // 节点不可用时仍能从本地章节导出
+    let offline = |store| {
+        Downloader::with_source(Arc::new(api(&[DEAD_NODE])))
+            .with_chapters(store)
+            .with_library(library.clone())
+    };
+    let save_path = dir.join("export.epub").to_string_lossy().to_string();
+    let result = offline(store)
+        .export(BOOK_ID, "epub", &save_path, &ExportOptions::default())
+        .unwrap();
+    assert_eq!(result.status, DownloadStatus::Completed);
+    assert!(epub_contains(&save_path, "cover.png"));
+    assert!(!epub_contains(&save_path, "cover.svg"));
+
+    let empty = || offline(ChapterStore::new(dir.join("empty-chapters")));
+    let result = empty()
+        .export(BOOK_ID, "epub", &save_path, &ExportOptions::default())
+        .unwrap();
+    assert_eq!(result.status, DownloadStatus::Failed);
+    assert_eq!(result.failed_chapters.len(), 3);
+
+    // 本地没有封面时使用生成的卡片
+    let options = ExportOptions {
+        missing_chapters: Some(MissingChapterPolicy::Placeholder),
+        ..ExportOptions::default()
+    };
+    let result = empty().export(BOOK_ID, "epub", &save_path, &options).unwrap();
+    assert_eq!(result.status, DownloadStatus::Completed);
+    assert!(epub_contains(&save_path, "cover.svg"));
+    assert!(!epub_contains(&save_path, "cover.png"));
+}
+
+/// zip 中的文件名不压缩，可以直接查找
+fn epub_contains(path: &str, name: &str) -> bool {
+    let epub = std::fs::read(path).unwrap();
+    epub.windows(name.len()).any(|window| window == name.as_bytes())
+}
+
+/// 模拟节点收到的某个接口的请求数
+async fn requests(server: &MockServer, endpoint: &str) -> usize {
+    let requests = server.received_requests().await.unwrap();
+    requests.iter().filter(|r| r.url.path() == endpoint).count()
+}
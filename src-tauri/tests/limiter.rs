@@ -0,0 +1,61 @@
+//! 按节点限速
+
+use std::time::{Duration, Instant};
+use tomato_novel_manager_lib::limiter::{RateLimiter, DEFAULT_RATE};
+use tomato_novel_manager_lib::sources::SourceStore;
+
+const NODE: &str = "http://node.test";
+
+#[tokio::test]
+async fn requests_beyond_burst_wait_for_tokens() {
+    let limiter = RateLimiter::default();
+
+    let started = Instant::now();
+    for _ in 0..10 {
+        limiter.acquire(NODE, 100.0).await;
+    }
+    assert!(started.elapsed() < Duration::from_millis(20));
+
+    // 令牌用完后按每秒 100 个补充
+    for _ in 0..5 {
+        limiter.acquire(NODE, 100.0).await;
+    }
+    assert!(started.elapsed() >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn overloaded_node_is_slowed_down() {
+    let limiter = RateLimiter::default();
+    limiter.acquire(NODE, 100.0).await;
+    limiter.acquire("http://other.test", 100.0).await;
+
+    limiter.record_failure(NODE, Some(429));
+
+    // 429 之后不再使用积攒的令牌，且速率减半
+    let started = Instant::now();
+    limiter.acquire(NODE, 100.0).await;
+    assert!(started.elapsed() >= Duration::from_millis(20));
+
+    let started = Instant::now();
+    limiter.acquire("http://other.test", 100.0).await;
+    assert!(started.elapsed() < Duration::from_millis(10));
+}
+
+#[test]
+fn invalid_rate_in_config_file_uses_default() {
+    let path = std::env::temp_dir().join(format!("tomato-sources-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"[
+            {"name": "a", "base_url": "http://a.test", "rate_limit": 0},
+            {"name": "b", "base_url": "http://b.test", "rate_limit": -5},
+            {"name": "c", "base_url": "http://c.test", "rate_limit": 2.5}
+        ]"#,
+    )
+    .unwrap();
+    let sources = SourceStore::load(path.clone());
+    assert_eq!(sources.rate_limit("http://a.test"), DEFAULT_RATE);
+    assert_eq!(sources.rate_limit("http://b.test"), DEFAULT_RATE);
+    assert_eq!(sources.rate_limit("http://c.test"), 2.5);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,114 @@
+//! 代理和网络设置
+
+mod common;
+
+use common::{api, fixture, mount, sources, BOOK_ID, DEAD_NODE};
+use std::collections::BTreeMap;
+use tomato_novel_manager_lib::api::FanqieApi;
+use tomato_novel_manager_lib::error::AppError;
+use tomato_novel_manager_lib::network::{NetworkSettings, NetworkStore};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer};
+
+/// 不存在的节点，只能通过代理访问
+const UPSTREAM: &str = "http://fanqie.invalid";
+
+#[tokio::test]
+async fn requests_go_through_configured_proxy() {
+    let proxy = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/detail"))
+        .and(header("X-Client", "cli"))
+        .respond_with(fixture("detail"))
+        .mount(&proxy)
+        .await;
+
+    let settings = NetworkSettings {
+        proxy: Some(proxy.uri()),
+        headers: BTreeMap::from([("X-Client".to_string(), "cli".to_string())]),
+        ..NetworkSettings::default()
+    };
+    let api = api(&[UPSTREAM]).with_network(NetworkStore::new(settings));
+
+    let book = api.get_book_detail(BOOK_ID).await.unwrap();
+    assert_eq!(book.book_name, "星海归途");
+}
+
+#[tokio::test]
+async fn node_proxy_overrides_global_proxy() {
+    let proxy = MockServer::start().await;
+    mount(&proxy, "/api/detail", fixture("detail")).await;
+    let sources = sources(&[UPSTREAM]);
+    let dead_proxy = NetworkStore::new(NetworkSettings {
+        proxy: Some(DEAD_NODE.to_string()),
+        ..NetworkSettings::default()
+    });
+    let api = FanqieApi::with_sources(sources.clone()).with_network(dead_proxy);
+
+    let results = api.test_connection(&api.network_settings(), None).await.unwrap();
+    assert!(!results[0].ok);
+    assert_eq!(results[0].proxy.as_deref(), Some(DEAD_NODE));
+
+    sources.set_proxy(UPSTREAM, Some(proxy.uri())).unwrap();
+    let book = api.get_book_detail(BOOK_ID).await.unwrap();
+    assert_eq!(book.book_id, BOOK_ID);
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+    let store = NetworkStore::default();
+    for settings in [
+        NetworkSettings {
+            proxy: Some("ftp://127.0.0.1:21".to_string()),
+            ..NetworkSettings::default()
+        },
+        NetworkSettings {
+            read_timeout_secs: 0,
+            ..NetworkSettings::default()
+        },
+        NetworkSettings {
+            headers: BTreeMap::from([("bad header".to_string(), "x".to_string())]),
+            ..NetworkSettings::default()
+        },
+    ] {
+        let err = AppError::from(store.set(settings).unwrap_err());
+        assert_eq!(err.code(), "invalid_input");
+    }
+    assert_eq!(store.get().read_timeout_secs, 30);
+}
+
+#[test]
+fn old_timeout_setting_is_read_as_read_timeout() {
+    let path = std::env::temp_dir().join(format!("tomato-network-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"timeout_secs": 45}"#).unwrap();
+    let store = NetworkStore::load(path.clone());
+    assert_eq!(store.get().read_timeout_secs, 45);
+    assert_eq!(store.get().connect_timeout_secs, 10);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn concurrent_saves_do_not_clobber_each_other() {
+    let dir = std::env::temp_dir().join(format!("tomato-network-save-{}", std::process::id()));
+    let path = dir.join("network.json");
+    let store = NetworkStore::load(path.clone());
+
+    // 每次保存使用各自的临时文件，同时保存不会互相截断
+    std::thread::scope(|scope| {
+        for secs in 20..28 {
+            let store = &store;
+            scope.spawn(move || {
+                let settings = NetworkSettings {
+                    read_timeout_secs: secs,
+                    ..NetworkSettings::default()
+                };
+                store.set(settings).unwrap();
+            });
+        }
+    });
+
+    let saved = NetworkStore::load(path).get().read_timeout_secs;
+    assert!((20..28).contains(&saved));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
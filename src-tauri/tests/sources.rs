@@ -0,0 +1,22 @@
+//! 节点配置的读取和保存
+
+use tomato_novel_manager_lib::sources::SourceStore;
+
+#[test]
+fn corrupt_config_is_backed_up_before_saving() {
+    let dir = std::env::temp_dir().join(format!("tomato-sources-corrupt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("sources.json");
+    let corrupt = r#"[{"name": "a", "base_url": "http://a.test",]"#;
+    std::fs::write(&path, corrupt).unwrap();
+
+    let sources = SourceStore::load(path.clone());
+    assert!(!sources.list().is_empty());
+
+    // 之后保存默认节点也不会覆盖用户原来的内容
+    let backup = dir.join("sources.json.bak");
+    assert_eq!(std::fs::read_to_string(&backup).unwrap(), corrupt);
+    assert!(!path.exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
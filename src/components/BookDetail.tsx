@@ -9,6 +9,7 @@ import {
   DownloadResult,
   getChapters,
   downloadBook,
+  errorMessage,
 } from "../lib/api";
 import "./BookDetail.css";
 
@@ -17,6 +18,12 @@ interface BookDetailProps {
   onBack: () => void;
 }
 
+// 有缺失章节的下载虽然成功，也要提醒用户
+function resultClass(result: DownloadResult): string {
+  if (!result.success) return "error";
+  return result.failed_chapters.length > 0 ? "warning" : "success";
+}
+
 export function BookDetail({ book, onBack }: BookDetailProps) {
   const [chapters, setChapters] = useState<Chapter[]>([]);
   const [loading, setLoading] = useState(true);
@@ -49,7 +56,7 @@ export function BookDetail({ book, onBack }: BookDetailProps) {
       const data = await getChapters(book.book_id);
       setChapters(data);
     } catch (err) {
-      setError(err instanceof Error ? err.message : "获取章节列表失败");
+      setError(errorMessage(err, "获取章节列表失败"));
     } finally {
       setLoading(false);
     }
@@ -113,8 +120,10 @@ export function BookDetail({ book, onBack }: BookDetailProps) {
     } catch (err) {
       setResult({
         success: false,
-        error: err instanceof Error ? err.message : String(err),
+        status: "failed",
+        error: errorMessage(err, String(err)),
         book_name: book.book_name,
+        failed_chapters: [],
       });
     } finally {
       setDownloading(false);
@@ -216,8 +225,24 @@ export function BookDetail({ book, onBack }: BookDetailProps) {
         )}
 
         {result && (
-          <div className={`result-message ${result.success ? "success" : "error"}`}>
-            {result.success ? (
+          <div className={`result-message ${resultClass(result)}`}>
+            {result.success && result.failed_chapters.length > 0 ? (
+              <>
+                <span className="result-icon">⚠️</span>
+                <div>
+                  <span>
+                    下载完成，但有 {result.failed_chapters.length} 个章节缺失，已用占位文本代替。文件保存到: {result.file_path}
+                  </span>
+                  <ul className="failed-chapters">
+                    {result.failed_chapters.map((ch) => (
+                      <li key={ch.id}>
+                        {ch.title}：{ch.error}
+                      </li>
+                    ))}
+                  </ul>
+                </div>
+              </>
+            ) : result.success ? (
               <>
                 <span className="result-icon">✅</span>
                 <span>下载完成！文件保存到: {result.file_path}</span>
@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { searchBooks, BookInfo, SearchResult } from "../lib/api";
+import { searchBooks, errorMessage, BookInfo, SearchResult } from "../lib/api";
 import "./SearchView.css";
 
 interface SearchViewProps {
@@ -34,7 +34,7 @@ export function SearchView({ onSelectBook }: SearchViewProps) {
       // 保存搜索关键词到缓存
       localStorage.setItem(LAST_KEYWORD_KEY, keyword.trim());
     } catch (err) {
-      setError(err instanceof Error ? err.message : "搜索失败");
+      setError(errorMessage(err, "搜索失败"));
     } finally {
       setLoading(false);
     }
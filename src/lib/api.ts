@@ -31,6 +31,15 @@ export interface DownloadOptions {
   format: string;
   start_chapter?: number;
   end_chapter?: number;
+  concurrency?: number;
+  retry_rounds?: number;
+  missing_chapters?: "placeholder" | "fail";
+}
+
+export interface ExportOptions {
+  start_chapter?: number;
+  end_chapter?: number;
+  missing_chapters?: "placeholder" | "fail";
 }
 
 export interface DownloadProgress {
@@ -41,35 +50,290 @@ export interface DownloadProgress {
   book_id: string;
 }
 
+export interface FailedChapter {
+  id: string;
+  title: string;
+  index: number;
+  error: string;
+}
+
+export type DownloadStatus = "completed" | "failed" | "cancelled";
+
 export interface DownloadResult {
   success: boolean;
+  status: DownloadStatus;
   file_path?: string;
   error?: string;
   book_name: string;
+  failed_chapters: FailedChapter[];
+}
+
+export interface UpdateResult extends DownloadResult {
+  new_chapters: number;
+}
+
+export type QueueStatus = "pending" | "running" | "completed" | "failed" | "cancelled";
+
+export interface QueueItem {
+  job_id: number;
+  book_id: string;
+  options: DownloadOptions;
+  kind: "download" | "update";
+  status: QueueStatus;
+  file_path?: string;
+  error?: string;
+  failed_chapters: FailedChapter[];
+  added_at: number;
+}
+
+export interface LibraryEntry {
+  book_info: BookInfo;
+  options: DownloadOptions;
+  format: string;
+  file_path: string;
+  downloaded_at: number;
+  updated_at: number;
+  chapter_count: number;
+  downloaded_chapters: number;
+}
+
+export interface FollowedBook {
+  book_id: string;
+  book_name: string;
+  chapter_count: number;
+  status?: string;
+  auto_download: boolean;
+  followed_at: number;
+  checked_at: number;
+}
+
+export interface BookUpdate {
+  book_id: string;
+  book_name: string;
+  old_chapter_count: number;
+  new_chapter_count: number;
+  new_chapters: Chapter[];
+  old_status?: string;
+  new_status?: string;
+  enqueued: boolean;
 }
 
 export interface ApiSource {
   name: string;
   base_url: string;
+  enabled: boolean;
+  rate_limit?: number;
+  proxy?: string;
+}
+
+export interface NetworkSettings {
+  proxy?: string;
+  connect_timeout_secs: number;
+  read_timeout_secs: number;
+  user_agent?: string;
+  headers: Record<string, string>;
+}
+
+export interface ConnectionTest {
+  base_url: string;
+  proxy?: string;
+  ok: boolean;
+  status?: number;
+  latency_ms: number;
+  error?: string;
+}
+
+export interface SourceHealth {
+  base_url: string;
+  latency_ms?: number;
+  error_rate: number;
+  samples: number;
+  score?: number;
+  last_error?: string;
+  checked_at?: number;
+  circuit_open: boolean;
+}
+
+export interface NodeAttempt {
+  base_url: string;
+  status?: number;
+  elapsed_ms: number;
+  error: string;
+  skipped: boolean;
+}
+
+export type AppErrorCode =
+  | "book_removed"
+  | "no_chapters"
+  | "all_sources_down"
+  | "parse_error"
+  | "schema_changed"
+  | "io_error"
+  | "cancelled"
+  | "not_found"
+  | "already_exists"
+  | "invalid_input"
+  | "other";
+
+// 后端命令返回的错误
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  attempts?: NodeAttempt[];
+}
+
+export function isAppError(err: unknown): err is AppError {
+  return !!err && typeof (err as AppError).code === "string" && typeof (err as AppError).message === "string";
+}
+
+export function errorMessage(err: unknown, fallback: string): string {
+  if (err instanceof Error) return err.message;
+  if (typeof err === "string") return err;
+  if (isAppError(err)) return err.message;
+  return fallback;
 }
 
 // API 调用封装
-export async function searchBooks(keyword: string, offset = 0): Promise<SearchResult> {
-  return await invoke("search_books", { keyword, offset });
+export async function searchBooks(
+  keyword: string,
+  offset = 0,
+  refresh = false
+): Promise<SearchResult> {
+  return await invoke("search_books", { keyword, offset, refresh });
 }
 
-export async function getBookDetail(bookId: string): Promise<BookInfo> {
-  return await invoke("get_book_detail", { bookId });
+export async function getBookDetail(bookId: string, refresh = false): Promise<BookInfo> {
+  return await invoke("get_book_detail", { bookId, refresh });
 }
 
-export async function getChapters(bookId: string): Promise<Chapter[]> {
-  return await invoke("get_chapters", { bookId });
+export async function getChapters(bookId: string, refresh = false): Promise<Chapter[]> {
+  return await invoke("get_chapters", { bookId, refresh });
+}
+
+export async function clearCache(): Promise<void> {
+  return await invoke("clear_cache");
 }
 
 export async function downloadBook(options: DownloadOptions): Promise<DownloadResult> {
   return await invoke("download_book", { options });
 }
 
+export async function updateBook(bookId: string): Promise<UpdateResult> {
+  return await invoke("update_book", { bookId });
+}
+
+export async function cancelDownload(bookId: string): Promise<void> {
+  return await invoke("cancel_download", { bookId });
+}
+
+export async function pauseDownload(bookId: string): Promise<void> {
+  return await invoke("pause_download", { bookId });
+}
+
+export async function resumeDownload(bookId: string): Promise<void> {
+  return await invoke("resume_download", { bookId });
+}
+
+export async function enqueueDownload(options: DownloadOptions): Promise<void> {
+  return await invoke("enqueue_download", { options });
+}
+
+export async function listQueue(): Promise<QueueItem[]> {
+  return await invoke("list_queue");
+}
+
+export async function reorderQueue(bookIds: string[]): Promise<void> {
+  return await invoke("reorder_queue", { bookIds });
+}
+
+export async function removeFromQueue(bookId: string): Promise<void> {
+  return await invoke("remove_from_queue", { bookId });
+}
+
+export async function setQueueConcurrency(maxConcurrent: number): Promise<void> {
+  return await invoke("set_queue_concurrency", { maxConcurrent });
+}
+
+export async function listLibrary(keyword?: string): Promise<LibraryEntry[]> {
+  return await invoke("list_library", { keyword });
+}
+
+export async function getLibraryEntry(bookId: string): Promise<LibraryEntry | null> {
+  return await invoke("get_library_entry", { bookId });
+}
+
+export async function removeLibraryEntry(bookId: string, deleteFile = false): Promise<void> {
+  return await invoke("remove_library_entry", { bookId, deleteFile });
+}
+
+export async function exportBook(
+  bookId: string,
+  format: string,
+  savePath: string,
+  options?: ExportOptions
+): Promise<DownloadResult> {
+  return await invoke("export_book", { bookId, format, savePath, options });
+}
+
+export async function followBook(bookId: string, autoDownload = false): Promise<FollowedBook> {
+  return await invoke("follow_book", { bookId, autoDownload });
+}
+
+export async function unfollowBook(bookId: string): Promise<void> {
+  return await invoke("unfollow_book", { bookId });
+}
+
+export async function listFollowed(): Promise<FollowedBook[]> {
+  return await invoke("list_followed");
+}
+
+export async function checkUpdates(): Promise<BookUpdate[]> {
+  return await invoke("check_updates");
+}
+
 export async function getApiSources(): Promise<ApiSource[]> {
   return await invoke("get_api_sources");
 }
+
+export async function getSourceHealth(probe = false): Promise<SourceHealth[]> {
+  return await invoke("get_source_health", { probe });
+}
+
+export async function addApiSource(name: string, baseUrl: string): Promise<void> {
+  return await invoke("add_api_source", { name, baseUrl });
+}
+
+export async function removeApiSource(baseUrl: string): Promise<void> {
+  return await invoke("remove_api_source", { baseUrl });
+}
+
+export async function setApiSourceEnabled(baseUrl: string, enabled: boolean): Promise<void> {
+  return await invoke("set_api_source_enabled", { baseUrl, enabled });
+}
+
+export async function setApiSourceRateLimit(baseUrl: string, rateLimit?: number): Promise<void> {
+  return await invoke("set_api_source_rate_limit", { baseUrl, rateLimit });
+}
+
+export async function setApiSourceProxy(baseUrl: string, proxy?: string): Promise<void> {
+  return await invoke("set_api_source_proxy", { baseUrl, proxy });
+}
+
+export async function reorderApiSources(baseUrls: string[]): Promise<void> {
+  return await invoke("reorder_api_sources", { baseUrls });
+}
+
+export async function getNetworkSettings(): Promise<NetworkSettings> {
+  return await invoke("get_network_settings");
+}
+
+export async function setNetworkSettings(settings: NetworkSettings): Promise<void> {
+  return await invoke("set_network_settings", { settings });
+}
+
+export async function testConnection(
+  settings?: NetworkSettings,
+  baseUrl?: string
+): Promise<ConnectionTest[]> {
+  return await invoke("test_connection", { settings, baseUrl });
+}